@@ -0,0 +1,11 @@
+#version 330 core
+
+out vec4 Color;
+
+uniform vec3 LineColor;
+
+
+void main()
+{
+    Color = vec4(LineColor, 1.0);
+}
@@ -0,0 +1,18 @@
+#version 330 core
+
+in vec3 Direction;
+out vec4 Color;
+
+uniform samplerCube Sky;
+uniform int IsHdr;
+
+
+void main()
+{
+    vec3 color = texture(Sky, Direction).rgb;
+    if (IsHdr != 0) {
+        // Reinhard tone mapping, then back to the display gamma.
+        color = pow(color / (color + 1.0), vec3(1.0 / 2.2));
+    }
+    Color = vec4(color, 1.0);
+}
@@ -0,0 +1,17 @@
+#version 330 core
+
+layout (location = 0) in vec3 Position;
+out vec3 Direction;
+
+uniform mat4 Rotation;
+uniform mat4 Projection;
+
+
+void main()
+{
+    // Only the rotation is applied, so the sky stays around the camera.
+    // The depth test is off while it is drawn, z only has to stay in the clip volume.
+    vec4 p = Projection * Rotation * vec4(Position, 1.0);
+    gl_Position = vec4(p.xy, 0.0, p.w);
+    Direction = Position;
+}
@@ -0,0 +1,12 @@
+#version 330 core
+
+in vec2 UV;
+out vec4 Color;
+
+uniform sampler2D Panel;
+
+
+void main()
+{
+    Color = texture(Panel, UV);
+}
@@ -0,0 +1,15 @@
+#version 330 core
+
+// Corner of the unit square, also the texture coordinate.
+layout (location = 0) in vec2 Position;
+out vec2 UV;
+
+// Lower left and upper right corners in normalized device coordinates.
+uniform vec4 Rect;
+
+
+void main()
+{
+    UV = Position;
+    gl_Position = vec4(mix(Rect.xy, Rect.zw, Position), 0.0, 1.0);
+}
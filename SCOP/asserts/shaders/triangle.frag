@@ -1,18 +1,110 @@
 #version 330 core
 
-out vec3 Color;
+// Must match MAX_POINT_LIGHTS in light.rs.
+#define MAX_POINT_LIGHTS 4
+
+out vec4 Color;
 
 in vec3 fragmentColor;
 in vec2 UV;
+in vec3 fragmentNormal;
+in vec3 fragmentPosition;
+in vec3 barycentric;
+in vec4 fragmentTangent;
 uniform sampler2D myTextureSampler;
-uniform int IsTexture;
+uniform float TextureBlend;
+uniform int AlphaMode;
+uniform float AlphaCutoff;
+uniform vec3 Ambient;
+uniform int IsLighting;
+uniform vec3 KeyLightDirection;
+uniform vec3 KeyLightColor;
+uniform int PointLightCount;
+uniform vec3 PointLightPosition[MAX_POINT_LIGHTS];
+uniform vec3 PointLightColor[MAX_POINT_LIGHTS];
+uniform float Shininess;
+uniform float SpecularStrength;
+// 0: faces only, 1: polygon edges over the faces, 2: edges over the fill color.
+uniform int WireMode;
+uniform vec3 WireColor;
+uniform vec3 FillColor;
+// 1: front faces and back faces in two colors, to find flipped faces.
+uniform int FaceDebug;
+// 1: the normals are perturbed by the tangent space normal map.
+uniform int HasNormalMap;
+uniform sampler2D NormalMap;
+
+const vec3 FrontFaceColor = vec3(0.2, 0.45, 0.9);
+const vec3 BackFaceColor = vec3(0.9, 0.2, 0.2);
+
+
+// Blinn-Phong, in camera space with the camera at the origin.
+vec3 shade(vec3 albedo, vec3 normal, vec3 view, vec3 light, vec3 lightColor)
+{
+    float diffuse = max(dot(normal, light), 0.0);
+    vec3 halfway = normalize(light + view);
+    float specular = diffuse > 0.0 ? pow(max(dot(normal, halfway), 0.0), Shininess) : 0.0;
+    return (albedo * diffuse + SpecularStrength * specular) * lightColor;
+}
+
+vec3 lighting(vec3 albedo)
+{
+    vec3 view = normalize(-fragmentPosition);
+    vec3 normal = normalize(fragmentNormal);
+    // Faces of open or inconsistently wound models are lit from both sides.
+    float side = dot(normal, view) < 0.0 ? -1.0 : 1.0;
+    if (HasNormalMap == 1) {
+        // The world matrix scales the model and the interpolation skews
+        // the vectors, so the tangent is made unit and perpendicular to the
+        // normal again (Gram-Schmidt) before the bitangent is built from them.
+        vec3 tangent = normalize(fragmentTangent.xyz - normal * dot(normal, fragmentTangent.xyz));
+        vec3 bitangent = fragmentTangent.w * cross(normal, tangent);
+        vec3 texel = texture(NormalMap, UV).xyz * 2.0 - 1.0;
+        normal = normalize(texel.x * tangent + texel.y * bitangent + texel.z * normal);
+    }
+    normal *= side;
+    vec3 color = 0.2 * Ambient * albedo;
+    color += shade(albedo, normal, view, normalize(KeyLightDirection), KeyLightColor);
+    for (int i = 0; i < PointLightCount; i++) {
+        vec3 light = PointLightPosition[i] - fragmentPosition;
+        float distance = length(light);
+        float attenuation = 1.0 / (1.0 + 0.1 * distance * distance);
+        color += attenuation * shade(albedo, normal, view, light / distance, PointLightColor[i]);
+    }
+    return color;
+}
 
+// 1 on the polygon edges, fading out over about a pixel. The diagonals of
+// the triangulation have their coordinate raised by 1, so they never show.
+float edge()
+{
+    vec3 width = fwidth(barycentric);
+    vec3 inside = smoothstep(vec3(0.0), 1.5 * width, barycentric);
+    return 1.0 - min(min(inside.x, inside.y), inside.z);
+}
 
 void main()
 {
-    if (IsTexture == 0) {
-        Color = fragmentColor;
+    vec4 color = mix(vec4(fragmentColor, 1.0), texture( myTextureSampler, UV ), TextureBlend);
+    if (AlphaMode == 0) {
+        color.a = 1.0;
+    } else if (AlphaMode == 1) {
+        if (color.a < AlphaCutoff) {
+            discard;
+        }
+        color.a = 1.0;
+    }
+    if (FaceDebug == 1) {
+        color = vec4(gl_FrontFacing ? FrontFaceColor : BackFaceColor, 1.0);
+    }
+    if (IsLighting == 0) {
+        Color = vec4(color.rgb * Ambient, color.a);
     } else {
-        Color = texture( myTextureSampler, UV ).rgb;
+        Color = vec4(lighting(color.rgb), color.a);
+    }
+    if (WireMode == 1) {
+        Color.rgb = mix(Color.rgb, WireColor, edge());
+    } else if (WireMode == 2) {
+        Color = vec4(mix(FillColor, WireColor, edge()), 1.0);
     }
 }
@@ -3,9 +3,17 @@
 layout (location = 0) in vec3 Position;
 layout (location = 1) in vec3 vertexColor;
 layout (location = 2) in vec2 vertexUV;
+layout (location = 3) in vec3 vertexNormal;
+layout (location = 4) in vec3 vertexBarycentric;
+layout (location = 5) in vec4 vertexTangent;
 out vec3 fragmentColor;
 out vec2 UV;
+out vec3 fragmentNormal;
+out vec3 fragmentPosition;
+out vec3 barycentric;
+out vec4 fragmentTangent;
 
+uniform mat4 World;
 uniform mat4 Center;
 uniform mat4 Scale;
 uniform mat4 Translation;
@@ -16,7 +24,14 @@ uniform mat4 Projection;
 void main()
 {
     vec4 v = vec4(Position, 1.0);
-    gl_Position = Projection * Translation * Rotation * Scale * Center * v;
+    vec4 position = Translation * Rotation * Scale * Center * World * v;
+    gl_Position = Projection * position;
+    fragmentPosition = position.xyz;
     fragmentColor = vertexColor;
     UV = vertexUV;
+    barycentric = vertexBarycentric;
+    // The scales are uniform, so the normals and tangents only need the
+    // rotations; their length is fixed in the fragment shader.
+    fragmentNormal = mat3(Rotation * World) * vertexNormal;
+    fragmentTangent = vec4(mat3(Rotation * World) * vertexTangent.xyz, vertexTangent.w);
 }
@@ -0,0 +1,89 @@
+extern crate proc_macro;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Expr, Field, Fields, Lit, Meta};
+
+
+// Generates `vertex_attrib_pointers()`, which calls the
+// `vertex_attrib_pointer(stride, location, offset)` function of each field
+// type with the location given by `#[location = N]`.
+#[proc_macro_derive(VertexAttribPointers, attributes(location))]
+pub fn vertex_attrib_pointers_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let ast = syn::parse_macro_input!(input as DeriveInput);
+    generate_impl(&ast)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+fn generate_impl(ast: &DeriveInput) -> syn::Result<TokenStream> {
+    let ident = &ast.ident;
+    let (impl_generics, type_generics, where_clause) = ast.generics.split_for_impl();
+    let fields_vertex_attrib_pointer = generate_vertex_attrib_pointer_calls(ast)?;
+
+    Ok(quote! {
+        impl #impl_generics #ident #type_generics #where_clause {
+            #[allow(unused_variables)]
+            pub fn vertex_attrib_pointers() {
+                let stride = ::std::mem::size_of::<Self>();
+                let offset = 0;
+
+                #(#fields_vertex_attrib_pointer)*
+            }
+        }
+    })
+}
+
+fn generate_vertex_attrib_pointer_calls(ast: &DeriveInput) -> syn::Result<Vec<TokenStream>> {
+    let error = |message| Err(syn::Error::new_spanned(&ast.ident, message));
+    match &ast.data {
+        Data::Enum(_) => error("VertexAttribPointers can not be implemented for enums"),
+        Data::Union(_) => error("VertexAttribPointers can not be implemented for unions"),
+        Data::Struct(s) => match &s.fields {
+            Fields::Unit => error("VertexAttribPointers can not be implemented for Unit structs"),
+            Fields::Unnamed(_) => error("VertexAttribPointers can not be implemented for Tuple structs"),
+            Fields::Named(fields) => fields.named
+                .iter()
+                .map(generate_struct_field_vertex_attrib_pointer_call)
+                .collect(),
+        },
+    }
+}
+
+fn generate_struct_field_vertex_attrib_pointer_call(field: &Field) -> syn::Result<TokenStream> {
+    let field_name = field.ident.as_ref().map(|i| i.to_string()).unwrap_or_default();
+    let location_attr = field
+        .attrs
+        .iter()
+        .find(|a| a.path().is_ident("location"))
+        .ok_or_else(|| syn::Error::new_spanned(
+            field,
+            format!("Field {} is missing #[location = ?] attribute", field_name),
+        ))?;
+
+    // Both `#[location = 0]` and the older `#[location = "0"]` are accepted.
+    let location_error = || syn::Error::new_spanned(
+        location_attr,
+        format!("Field {} location attribute value must be an integer", field_name),
+    );
+    let location_value: usize = match &location_attr.meta {
+        Meta::NameValue(name_value) => match &name_value.value {
+            Expr::Lit(expr) => match &expr.lit {
+                Lit::Int(i) => i.base10_parse().map_err(|_| location_error())?,
+                Lit::Str(s) => s.value().parse().map_err(|_| location_error())?,
+                _ => return Err(location_error()),
+            },
+            _ => return Err(location_error()),
+        },
+        _ => return Err(location_error()),
+    };
+
+    let field_ty = &field.ty;
+    Ok(quote! {
+        let location = #location_value;
+        unsafe {
+            <#field_ty>::vertex_attrib_pointer(stride, location, offset);
+        }
+        let offset = offset + ::std::mem::size_of::<#field_ty>();
+    })
+}
@@ -0,0 +1,196 @@
+use std::str::FromStr;
+
+use crate::texture::options::TextureOptions;
+use crate::img_loader::ops::ImageOp;
+use crate::texture::procedural::Procedural;
+use crate::model::normal::{NormalMode, DEFAULT_CREASE_ANGLE};
+use crate::model::uv::UvProjection;
+use crate::model::subdivide::SubdivisionScheme;
+use crate::model::repair::DEFAULT_MAX_HOLE_EDGES;
+use crate::model::DEFAULT_FADE_MS;
+use crate::culling::{Winding, DEFAULT_FRONT_FACE};
+
+
+const USAGE: &str = "usage: scop_display [options] [file.obj ...]
+options:
+    --texture <path.bmp|path.hdr>  (can be repeated, the n-th one starts on the n-th file)
+    --normal-map <path.bmp>  (tangent space, OpenGL convention, N switches it)
+    --wrap <repeat|mirror|clamp>
+    --min-filter <nearest|linear|nearest-mipmap-nearest|linear-mipmap-nearest|
+                  nearest-mipmap-linear|linear-mipmap-linear>
+    --mag-filter <nearest|linear>
+    --anisotropy <level>
+    --mip-filter <box|kaiser|lanczos>
+    --flip-v, --flip-h
+    --resize <width>x<height>[:bilinear|:bicubic]
+    --crop <x>,<y>,<width>,<height>
+    --swizzle <pattern of r,g,b,a,0,1>
+    --srgb-to-linear, --linear-to-srgb
+    --procedural <checker|grid|perlin|simplex|worley|marble|wood|bricks>[:key=value,...]
+                 keys: size, cells, octaves, seed, turbulence, mortar
+    --normals <flat|area|angle>
+    --crease <degrees>
+    --uv <faces|planar|box|spherical|cylindrical>
+    --fade-ms <milliseconds>  (color / texture transition, 0 to switch at once)
+    --weld-epsilon <distance>  (also merge vertices closer than it)
+    --simplify <triangles>  (edge collapses down to this triangle count)
+    --simplify-error <ratio>  (stop before moving the surface by this ratio of the model size)
+    --stats  (print the mesh statistics, I shows them in the window)
+    --repair  (remove degenerate and duplicate faces, orient the faces, fill small holes)
+    --repair-holes <edges>  (largest hole filled, 0 fills none)
+    --write-obj <path.obj>  (write the mesh as loaded, after --repair)
+    --lod <levels>  (simplified levels drawn by screen size, D cycles them)
+    --subdivide <levels>  (+ and - change the level)
+    --subdivision <auto|catmull-clark|loop>  (auto: loop for triangle meshes)
+    --subdivision-crease <degrees>  (edges sharper than it stay sharp)
+    --cull  (start with back faces culled, C switches it)
+    --front-face <ccw|cw>  (winding of the front faces on screen, default cw, W swaps it)
+    --parent <child>:<parent>  (numbers of the files, from 1, the child follows its parent)
+    --skybox <cross.bmp|panorama.hdr|+x,-x,+y,-y,+z,-z images>";
+
+
+#[derive(Debug)]
+pub struct Args {
+    pub model_paths: Vec<String>,
+    pub texture_paths: Vec<String>,
+    pub normal_map: Option<String>,
+    pub texture_options: TextureOptions,
+    pub image_ops: Vec<ImageOp>,
+    pub procedural: Option<Procedural>,
+    pub skybox: Option<String>,
+    pub normal_mode: NormalMode,
+    pub crease_angle: f32,
+    pub uv_projection: UvProjection,
+    pub fade_ms: f32,
+    pub weld_epsilon: Option<f32>,
+    pub simplify: Option<usize>,
+    pub simplify_error: Option<f32>,
+    pub lod_levels: usize,
+    pub subdivide: usize,
+    pub subdivision_scheme: SubdivisionScheme,
+    pub subdivision_crease: Option<f32>,
+    pub stats: bool,
+    pub repair: bool,
+    pub repair_holes: usize,
+    pub write_obj: Option<String>,
+    pub parents: Vec<(usize, usize)>,
+    pub cull: bool,
+    pub front_face: Winding,
+}
+
+impl Args {
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut result = Self {
+            model_paths: Vec::new(),
+            texture_paths: Vec::new(),
+            normal_map: None,
+            texture_options: TextureOptions::new(),
+            image_ops: Vec::new(),
+            procedural: None,
+            skybox: None,
+            normal_mode: NormalMode::Angle,
+            crease_angle: DEFAULT_CREASE_ANGLE,
+            uv_projection: UvProjection::Faces,
+            fade_ms: DEFAULT_FADE_MS,
+            weld_epsilon: None,
+            simplify: None,
+            simplify_error: None,
+            lod_levels: 0,
+            subdivide: 0,
+            subdivision_scheme: SubdivisionScheme::Auto,
+            subdivision_crease: None,
+            stats: false,
+            repair: false,
+            repair_holes: DEFAULT_MAX_HOLE_EDGES,
+            write_obj: None,
+            parents: Vec::new(),
+            cull: false,
+            front_face: DEFAULT_FRONT_FACE,
+        };
+        let mut iter = args.iter().skip(1);
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--texture" => result.texture_paths.push(Self::value(&mut iter, arg)?),
+                "--normal-map" => result.normal_map = Some(Self::value(&mut iter, arg)?),
+                "--wrap" => result.texture_options.wrap = Self::value(&mut iter, arg)?,
+                "--min-filter" => result.texture_options.min_filter = Self::value(&mut iter, arg)?,
+                "--mag-filter" => result.texture_options.mag_filter = Self::value(&mut iter, arg)?,
+                "--anisotropy" => result.texture_options.anisotropy = Self::value(&mut iter, arg)?,
+                "--mip-filter" => result.texture_options.mip_filter = Self::value(&mut iter, arg)?,
+                "--flip-v" => result.image_ops.push(ImageOp::FlipVertical),
+                "--flip-h" => result.image_ops.push(ImageOp::FlipHorizontal),
+                "--resize" => result.image_ops.push(ImageOp::parse_resize(Self::next(&mut iter, arg)?)?),
+                "--crop" => result.image_ops.push(ImageOp::parse_crop(Self::next(&mut iter, arg)?)?),
+                "--swizzle" => result.image_ops.push(ImageOp::parse_swizzle(Self::next(&mut iter, arg)?)?),
+                "--srgb-to-linear" => result.image_ops.push(ImageOp::SrgbToLinear),
+                "--linear-to-srgb" => result.image_ops.push(ImageOp::LinearToSrgb),
+                "--procedural" => result.procedural = Some(Self::value(&mut iter, arg)?),
+                "--normals" => result.normal_mode = Self::value(&mut iter, arg)?,
+                "--crease" => result.crease_angle = Self::value(&mut iter, arg)?,
+                "--uv" => result.uv_projection = Self::value(&mut iter, arg)?,
+                "--fade-ms" => result.fade_ms = Self::value(&mut iter, arg)?,
+                "--weld-epsilon" => result.weld_epsilon = Some(Self::value(&mut iter, arg)?),
+                "--simplify" => result.simplify = Some(Self::value(&mut iter, arg)?),
+                "--simplify-error" => result.simplify_error = Some(Self::value(&mut iter, arg)?),
+                "--stats" => result.stats = true,
+                "--repair" => result.repair = true,
+                "--repair-holes" => result.repair_holes = Self::value(&mut iter, arg)?,
+                "--write-obj" => result.write_obj = Some(Self::value(&mut iter, arg)?),
+                "--lod" => result.lod_levels = Self::value(&mut iter, arg)?,
+                "--subdivide" => result.subdivide = Self::value(&mut iter, arg)?,
+                "--subdivision" => result.subdivision_scheme = Self::value(&mut iter, arg)?,
+                "--subdivision-crease" => result.subdivision_crease = Some(Self::value(&mut iter, arg)?),
+                "--cull" => result.cull = true,
+                "--front-face" => result.front_face = Self::value(&mut iter, arg)?,
+                "--parent" => result.parents.push(Self::parse_parent(Self::next(&mut iter, arg)?)?),
+                "--skybox" => result.skybox = Some(Self::value(&mut iter, arg)?),
+                "-h" | "--help" => return Err(USAGE.to_string()),
+                _ if arg.starts_with("--") => {
+                    return Err(format!("error: unknown option: {}\n{}", arg, USAGE))
+                },
+                _ => result.model_paths.push(arg.clone()),
+            }
+        }
+        if result.write_obj.is_some() && result.model_paths.len() > 1 {
+            return Err(format!("error: --write-obj takes a single file\n{}", USAGE))
+        }
+        Ok(result)
+    }
+
+    // Textures and skyboxes are also looked for next to the first file.
+    pub fn model_path(&self) -> Option<&str> {
+        self.model_paths.first().map(|p| p.as_str())
+    }
+
+    // Both objects are numbered from 1, in the order of the files.
+    fn parse_parent(value: &str) -> Result<(usize, usize), String> {
+        let error = || format!("error: --parent: expected <child>:<parent>, got {}", value);
+        let (child, parent) = value.split_once(':').ok_or_else(error)?;
+        let child: usize = child.parse().map_err(|_| error())?;
+        let parent: usize = parent.parse().map_err(|_| error())?;
+        if child == 0 || parent == 0 {
+            return Err(error())
+        }
+        Ok((child - 1, parent - 1))
+    }
+
+    fn next<'a, I>(iter: &mut I, name: &str) -> Result<&'a str, String>
+    where
+        I: Iterator<Item = &'a String>,
+    {
+        iter.next()
+            .map(|s| s.as_str())
+            .ok_or_else(|| format!("error: {} needs a value\n{}", name, USAGE))
+    }
+
+    fn value<'a, T, I>(iter: &mut I, name: &str) -> Result<T, String>
+    where
+        T: FromStr,
+        T::Err: ToString,
+        I: Iterator<Item = &'a String>,
+    {
+        Self::next(iter, name)?
+            .parse::<T>()
+            .map_err(|e| format!("{}: {}", name, e.to_string()))
+    }
+}
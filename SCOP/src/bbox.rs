@@ -0,0 +1,108 @@
+use std::ffi::CString;
+
+use crate::gl_object::{VertexArray, Buffer};
+use crate::mat::{Matrix, Vec3d};
+use crate::mvp::{MVP, MVPMatrix};
+use crate::shader::Program;
+
+
+const LINE_COLOR: [f32; 3] = [1.0, 0.8, 0.2];
+
+
+// Axis-aligned bounding boxes, drawn as the lines of a unit cube moved
+// over each box.
+pub struct BoundingBoxOverlay {
+    program: Program,
+    vao: VertexArray,
+    _vbo: Buffer,
+    transform: MVPMatrix,
+    color_id: i32,
+    on: bool,
+}
+
+impl BoundingBoxOverlay {
+    pub fn new(mvp: &MVP) -> Result<Self, String> {
+        let program = Program::from_shaders_source(
+            &CString::new(include_str!("../asserts/shaders/line.vert")).map_err(|_| "error: line vertex shader".to_string())?,
+            &CString::new(include_str!("../asserts/shaders/line.frag")).map_err(|_| "error: line fragment shader".to_string())?
+        )?;
+        let corner = |i: usize| [
+            (i & 1) as f32,
+            ((i >> 1) & 1) as f32,
+            ((i >> 2) & 1) as f32,
+        ];
+        // The corners whose indices differ by one bit share an edge.
+        let mut lines: Vec<[f32; 3]> = Vec::with_capacity(24);
+        for i in 0..8 {
+            for bit in [1, 2, 4] {
+                if i & bit == 0 {
+                    lines.push(corner(i));
+                    lines.push(corner(i | bit));
+                }
+            }
+        }
+        let vao = VertexArray::new();
+        vao.bind();
+        let vbo = Buffer::array();
+        vbo.upload(&lines, gl::STATIC_DRAW);
+        unsafe {
+            gl::EnableVertexAttribArray(0);
+            gl::VertexAttribPointer(
+                0,
+                3,
+                gl::FLOAT,
+                gl::FALSE,
+                0,
+                std::ptr::null(),
+            );
+        }
+        let name = CString::new("LineColor").map_err(|_| "error: GetUniformLocation".to_string())?;
+        let color_id = unsafe { gl::GetUniformLocation(program.id(), name.as_ptr()) };
+        Ok(Self {
+            transform: MVPMatrix::new(program.id(), "Transform", mvp.model_view_projection())?,
+            program,
+            vao,
+            _vbo: vbo,
+            color_id,
+            on: false,
+        })
+    }
+
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+
+    pub fn switch(&mut self) {
+        self.on = !self.on;
+    }
+
+    // `boxes` take the unit cube to the world.
+    pub fn draw(&mut self, mvp: &MVP, boxes: &[Matrix]) {
+        if !self.on {
+            return;
+        }
+        let model_view_projection = mvp.model_view_projection();
+        self.program.set_used();
+        unsafe {
+            gl::Uniform3f(self.color_id, LINE_COLOR[0], LINE_COLOR[1], LINE_COLOR[2]);
+        }
+        self.vao.bind();
+        for matrix in boxes {
+            self.transform.matrix_update(model_view_projection.mul(matrix));
+            self.transform.set();
+            unsafe {
+                gl::DrawArrays(gl::LINES, 0, 24);
+            }
+        }
+    }
+}
+
+// Takes the unit cube to the box from `min` to `max`.
+pub fn box_matrix(min: &Vec3d, max: &Vec3d) -> Matrix {
+    Matrix::new([
+        [max.d0 - min.d0, 0.0, 0.0, min.d0],
+        [0.0, max.d1 - min.d1, 0.0, min.d1],
+        [0.0, 0.0, max.d2 - min.d2, min.d2],
+        [0.0, 0.0, 0.0, 1.0],
+    ])
+}
@@ -0,0 +1,108 @@
+use std::ffi::CString;
+use std::str::FromStr;
+
+
+// The projection looks down +z without flipping an axis, so the outside of a
+// counter-clockwise mesh is seen clockwise on screen.
+pub const DEFAULT_FRONT_FACE: Winding = Winding::Clockwise;
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Winding {
+    CounterClockwise,
+    Clockwise,
+}
+
+// Only set around the models: the skybox is seen from inside its cube, and
+// the overlays are drawn whatever their winding.
+#[derive(Debug)]
+pub struct Culling {
+    on: bool,
+    front_face: Winding,
+    face_debug: bool,
+    face_debug_id: i32,
+}
+
+impl FromStr for Winding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ccw" => Ok(Winding::CounterClockwise),
+            "cw" => Ok(Winding::Clockwise),
+            _ => Err(format!("error: unknown winding: {}", s)),
+        }
+    }
+}
+
+impl Winding {
+    fn other(self) -> Self {
+        match self {
+            Winding::CounterClockwise => Winding::Clockwise,
+            Winding::Clockwise => Winding::CounterClockwise,
+        }
+    }
+
+    fn to_gl(self) -> gl::types::GLenum {
+        match self {
+            Winding::CounterClockwise => gl::CCW,
+            Winding::Clockwise => gl::CW,
+        }
+    }
+}
+
+impl Culling {
+    pub fn new(on: bool, front_face: Winding) -> Self {
+        Self { on, front_face, face_debug: false, face_debug_id: 0 }
+    }
+
+    pub fn set_program(&mut self, program_id: gl::types::GLuint) -> Result<(), String> {
+        let name = CString::new("FaceDebug").map_err(|_| "error: GetUniformLocation".to_string())?;
+        self.face_debug_id = unsafe { gl::GetUniformLocation(program_id, name.as_ptr()) };
+        Ok(())
+    }
+
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+
+    pub fn switch(&mut self) {
+        self.on = !self.on;
+    }
+
+    pub fn front_face(&self) -> Winding {
+        self.front_face
+    }
+
+    pub fn switch_front_face(&mut self) {
+        self.front_face = self.front_face.other();
+    }
+
+    pub fn is_face_debug(&self) -> bool {
+        self.face_debug
+    }
+
+    pub fn switch_face_debug(&mut self) {
+        self.face_debug = !self.face_debug;
+    }
+
+    pub fn set(&self) {
+        unsafe {
+            if self.on {
+                gl::Enable(gl::CULL_FACE);
+                gl::CullFace(gl::BACK);
+            } else {
+                gl::Disable(gl::CULL_FACE);
+            }
+            gl::FrontFace(self.front_face.to_gl());
+            gl::Uniform1i(self.face_debug_id, self.face_debug as i32);
+        }
+    }
+
+    pub fn unset(&self) {
+        unsafe {
+            gl::Disable(gl::CULL_FACE);
+            gl::FrontFace(gl::CCW);
+        }
+    }
+}
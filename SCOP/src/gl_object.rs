@@ -0,0 +1,108 @@
+// Owning wrappers of GL object names, deleted on drop like shader::Program.
+// They must be dropped while the GL context is still alive.
+
+pub struct VertexArray {
+    id: gl::types::GLuint,
+}
+
+pub struct Buffer {
+    id: gl::types::GLuint,
+    target: gl::types::GLenum,
+}
+
+pub struct TextureObject {
+    id: gl::types::GLuint,
+    target: gl::types::GLenum,
+}
+
+impl VertexArray {
+    pub fn new() -> Self {
+        let mut id = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut id);
+        }
+        Self { id }
+    }
+
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindVertexArray(self.id);
+        }
+    }
+}
+
+impl Drop for VertexArray {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.id);
+        }
+    }
+}
+
+impl Buffer {
+    pub fn new(target: gl::types::GLenum) -> Self {
+        let mut id = 0;
+        unsafe {
+            gl::GenBuffers(1, &mut id);
+        }
+        Self { id, target }
+    }
+
+    pub fn array() -> Self {
+        Self::new(gl::ARRAY_BUFFER)
+    }
+
+    pub fn element_array() -> Self {
+        Self::new(gl::ELEMENT_ARRAY_BUFFER)
+    }
+
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindBuffer(self.target, self.id);
+        }
+    }
+
+    pub fn upload<T>(&self, data: &[T], usage: gl::types::GLenum) {
+        self.bind();
+        unsafe {
+            gl::BufferData(
+                self.target,
+                std::mem::size_of_val(data) as gl::types::GLsizeiptr,
+                data.as_ptr() as *const gl::types::GLvoid,
+                usage,
+            );
+        }
+    }
+}
+
+impl Drop for Buffer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &self.id);
+        }
+    }
+}
+
+impl TextureObject {
+    pub fn new(target: gl::types::GLenum) -> Self {
+        let mut id = 0;
+        unsafe {
+            gl::GenTextures(1, &mut id);
+        }
+        Self { id, target }
+    }
+
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindTexture(self.target, self.id);
+        }
+    }
+}
+
+impl Drop for TextureObject {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteTextures(1, &self.id);
+        }
+    }
+}
@@ -1,39 +1,184 @@
+pub mod bmp;
+pub mod mipmap;
+pub mod ops;
+pub mod hdr;
+
 use std::fs::File;
 use std::io::Read;
 use std::ffi::c_void;
 
 
+#[derive(Debug, Clone)]
 pub struct Image {
-    data: Vec<f32>,
+    data: PixelData,
     width: usize,
     height: usize,
+    format: PixelFormat,
+    color_space: ColorSpace,
+}
+
+// What the color channels hold, for the filters that average them. Alpha is
+// always linear.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorSpace {
+    Srgb,
+    Linear,
+}
+
+#[derive(Debug, Clone)]
+pub enum PixelData {
+    U8(Vec<u8>),
+    U16(Vec<u16>),
+    F32(Vec<f32>),
+}
+
+#[allow(clippy::upper_case_acronyms, dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PixelFormat {
+    R8,
+    // Gray + alpha, uploaded as RG8 and swizzled.
+    LA8,
+    RGB8,
+    RGBA8,
+    R16,
+    RGBA16,
+    RGBA16F,
+    R32F,
+    RGB32F,
+    RGBA32F,
+}
+
+impl PixelFormat {
+    pub fn channels(&self) -> usize {
+        match self {
+            PixelFormat::R8 | PixelFormat::R16 | PixelFormat::R32F => 1,
+            PixelFormat::LA8 => 2,
+            PixelFormat::RGB8 | PixelFormat::RGB32F => 3,
+            PixelFormat::RGBA8 | PixelFormat::RGBA16
+                | PixelFormat::RGBA16F | PixelFormat::RGBA32F => 4,
+        }
+    }
+
+    pub fn has_alpha(&self) -> bool {
+        matches!(self.channels(), 2 | 4)
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, PixelFormat::RGBA16F | PixelFormat::R32F | PixelFormat::RGB32F | PixelFormat::RGBA32F)
+    }
+
+    pub fn gl_internal_format(&self) -> gl::types::GLenum {
+        match self {
+            PixelFormat::R8 => gl::R8,
+            PixelFormat::LA8 => gl::RG8,
+            PixelFormat::RGB8 => gl::RGB8,
+            PixelFormat::RGBA8 => gl::RGBA8,
+            PixelFormat::R16 => gl::R16,
+            PixelFormat::RGBA16 => gl::RGBA16,
+            PixelFormat::RGBA16F => gl::RGBA16F,
+            PixelFormat::R32F => gl::R32F,
+            PixelFormat::RGB32F => gl::RGB32F,
+            PixelFormat::RGBA32F => gl::RGBA32F,
+        }
+    }
+
+    pub fn gl_format(&self) -> gl::types::GLenum {
+        match self.channels() {
+            1 => gl::RED,
+            2 => gl::RG,
+            3 => gl::RGB,
+            _ => gl::RGBA,
+        }
+    }
+
+    pub fn gl_type(&self) -> gl::types::GLenum {
+        match self {
+            PixelFormat::R8 | PixelFormat::LA8
+                | PixelFormat::RGB8 | PixelFormat::RGBA8 => gl::UNSIGNED_BYTE,
+            PixelFormat::R16 | PixelFormat::RGBA16 => gl::UNSIGNED_SHORT,
+            PixelFormat::RGBA16F => gl::HALF_FLOAT,
+            PixelFormat::R32F | PixelFormat::RGB32F | PixelFormat::RGBA32F => gl::FLOAT,
+        }
+    }
+
+    // R8 is gray and LA8 gray + alpha, not red and red + green.
+    pub fn gl_swizzle(&self) -> [gl::types::GLenum; 4] {
+        match self.channels() {
+            1 => [gl::RED, gl::RED, gl::RED, gl::ONE],
+            2 => [gl::RED, gl::RED, gl::RED, gl::GREEN],
+            _ => [gl::RED, gl::GREEN, gl::BLUE, gl::ALPHA],
+        }
+    }
+
+    fn empty_data(&self, len: usize) -> PixelData {
+        match self.gl_type() {
+            gl::UNSIGNED_BYTE => PixelData::U8(vec![0; len]),
+            gl::UNSIGNED_SHORT | gl::HALF_FLOAT => PixelData::U16(vec![0; len]),
+            _ => PixelData::F32(vec![0.0; len]),
+        }
+    }
 }
 
-enum Mode {
-    Gray,
-    RGB,
-    RGBA,
+impl PixelData {
+    pub fn len(&self) -> usize {
+        match self {
+            PixelData::U8(v) => v.len(),
+            PixelData::U16(v) => v.len(),
+            PixelData::F32(v) => v.len(),
+        }
+    }
+
+    fn is_type_of(&self, format: PixelFormat) -> bool {
+        matches!(
+            (self, format.gl_type()),
+            (PixelData::U8(_), gl::UNSIGNED_BYTE)
+                | (PixelData::U16(_), gl::UNSIGNED_SHORT | gl::HALF_FLOAT)
+                | (PixelData::F32(_), gl::FLOAT)
+        )
+    }
+
+    fn as_ptr(&self) -> *const c_void {
+        match self {
+            PixelData::U8(v) => v.as_ptr() as *const c_void,
+            PixelData::U16(v) => v.as_ptr() as *const c_void,
+            PixelData::F32(v) => v.as_ptr() as *const c_void,
+        }
+    }
 }
 
 impl Image {
     pub fn new(path: &str) -> Result<Self, String> {
         let data = Self::load_file(path)?;
-        let (width, height, mode) = Self::load_bmp_header(&data)
-            .map_err(|e| format!("{}: {}", e, path))?;
-        Ok(Image {
-            data: Self::load_bmp_data(&data, width, height, mode),
-            width,
-            height,
-        })
+        let image = if Self::is_hdr(&data) {
+            Self::from_hdr(&data)
+        } else {
+            Self::from_bmp(&data)
+        };
+        image.map_err(|e| format!("{}: {}", e, path))
     }
 
-    pub fn get_data(&self) -> &Vec<f32> {
-        &self.data
+    pub fn from_raw(width: usize, height: usize, format: PixelFormat, data: PixelData) -> Result<Self, String> {
+        if data.len() != width * height * format.channels() {
+            return Err(format!("error: image data size: {:?} {}x{}", format, width, height))
+        }
+        if !data.is_type_of(format) {
+            return Err(format!("error: image data type: {:?}", format))
+        }
+        Ok(Self { data, width, height, format, color_space: ColorSpace::default_for(format) })
+    }
+
+    pub fn blank(width: usize, height: usize, format: PixelFormat) -> Self {
+        let data = format.empty_data(width * height * format.channels());
+        Self { data, width, height, format, color_space: ColorSpace::default_for(format) }
     }
 
     #[allow(dead_code)]
+    pub fn get_data(&self) -> &PixelData {
+        &self.data
+    }
+
     pub fn get_ptr(&self) -> *const c_void {
-        self.data.as_ptr() as *const c_void
+        self.data.as_ptr()
     }
 
     pub fn get_width(&self) -> usize {
@@ -44,6 +189,24 @@ impl Image {
         self.height
     }
 
+    pub fn color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+
+    // Only changes how the values are read, not the values.
+    pub fn with_color_space(mut self, color_space: ColorSpace) -> Self {
+        self.color_space = color_space;
+        self
+    }
+
+    pub fn get_format(&self) -> PixelFormat {
+        self.format
+    }
+
+    pub fn has_alpha(&self) -> bool {
+        self.format.has_alpha()
+    }
+
     fn load_file(path: &str) -> Result<Vec<u8>, String> {
         let mut file = File::open(path)
             .map_err(|e| format!("error: {}: {}", path, e))?;
@@ -53,76 +216,101 @@ impl Image {
         Ok(buf)
     }
 
-    fn load_bmp_header(data: &Vec<u8>) -> Result<(usize, usize, Mode), String> {
-        // There should be 54bite in the header.
-        if data.len() < 54 || data[0] != 'B' as u8 || data[1] != 'M' as u8 {
-            return Err(format!("error: not bmp"))
-        }
-
-        let header_size = data[0x0A] as usize;
-        let image_size = data[0x22] as usize;
-        let width = data[0x12] as usize;
-        let height = data[0x16] as usize;
-        if header_size != 0 && header_size != 54 {
-            return Err(format!("error: not supported bmp: header_size != 54"))
+    // Reads one pixel as normalized RGBA, expanding gray the same way the texture swizzle does.
+    pub fn get_pixel(&self, x: usize, y: usize) -> [f32; 4] {
+        let channels = self.format.channels();
+        let offset = (y * self.width + x) * channels;
+        let mut value = [0.0; 4];
+        for (c, v) in value.iter_mut().enumerate().take(channels) {
+            *v = match (&self.data, self.format) {
+                (PixelData::U8(d), _) => d[offset + c] as f32 / 255.0,
+                (PixelData::U16(d), PixelFormat::RGBA16F) => f16_to_f32(d[offset + c]),
+                (PixelData::U16(d), _) => d[offset + c] as f32 / 65535.0,
+                (PixelData::F32(d), _) => d[offset + c],
+            };
         }
-        if image_size != 0 && image_size != width * height * 3 {
-            return Err(format!("error: not supported bmp: image_size != width * height * 3"))
+        match channels {
+            1 => [value[0], value[0], value[0], 1.0],
+            2 => [value[0], value[0], value[0], value[1]],
+            3 => [value[0], value[1], value[2], 1.0],
+            _ => value,
         }
-        if !Self::is_pow2(width * height) {
-            return Err(format!("error: not supported bmp: size != 2^n"))
-        }
-        let mode = if data.len() - 54 == width * height {
-            Mode::Gray
-        } else if data.len() - 54 == width * height * 3 {
-            Mode::RGB
-        } else if data.len() - 54 == width * height * 4 {
-            Mode::RGBA
-        } else {
-            return Err(format!("error: not supported bmp: data size"))
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, rgba: [f32; 4]) {
+        let channels = self.format.channels();
+        let offset = (y * self.width + x) * channels;
+        let luminance = 0.2126 * rgba[0] + 0.7152 * rgba[1] + 0.0722 * rgba[2];
+        let value = match channels {
+            1 => [luminance, 0.0, 0.0, 0.0],
+            2 => [luminance, rgba[3], 0.0, 0.0],
+            _ => rgba,
         };
-        Ok((width as usize, height as usize, mode))
+        let format = self.format;
+        for (c, v) in value.iter().enumerate().take(channels) {
+            match &mut self.data {
+                PixelData::U8(d) => d[offset + c] = (v.clamp(0.0, 1.0) * 255.0).round() as u8,
+                PixelData::U16(d) => d[offset + c] = if let PixelFormat::RGBA16F = format {
+                    f32_to_f16(*v)
+                } else {
+                    (v.clamp(0.0, 1.0) * 65535.0).round() as u16
+                },
+                PixelData::F32(d) => d[offset + c] = *v,
+            }
+        }
     }
 
-    fn is_pow2(value: usize) -> bool {
-        let mut value = value;
-        if value % 2 != 0 {
-            return false
+    #[allow(dead_code)]
+    pub fn convert(&self, format: PixelFormat) -> Self {
+        if format == self.format {
+            return self.clone()
         }
-        while value > 1 {
-            value /= 2;
-            if value != 1 && value % 2 != 0 {
-                return false
+        let mut img = Self::blank(self.width, self.height, format).with_color_space(self.color_space);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                img.set_pixel(x, y, self.get_pixel(x, y));
             }
         }
-        true
-    }
-
-    fn load_bmp_data(data: &Vec<u8>, width: usize, height: usize, mode: Mode) -> Vec<f32> {
-        let mut img_data = vec![0.0; 3 * width * height];
-        for i in 0..height {
-            for j in 0..width {
-                let img_offset = i * width * 3 + j * 3;
-                match mode {
-                    Mode::Gray => {
-                        let value = data[i * width + j + 54] as f32 / 256.0;
-                        img_data[img_offset + 0] = value;
-                        img_data[img_offset + 1] = value;
-                        img_data[img_offset + 2] = value;
-                    },
-                    _ => {
-                        let data_offset = match mode {
-                            Mode::RGB => i * width * 3 + j * 4 + 54,
-                            Mode::RGBA => i * width * 4 + j * 4 + 54,
-                            _ => 0,
-                        };
-                        img_data[img_offset + 0] = data[data_offset + 2] as f32 / 256.0;
-                        img_data[img_offset + 1] = data[data_offset + 1] as f32 / 256.0;
-                        img_data[img_offset + 2] = data[data_offset + 0] as f32 / 256.0;
-                    }
-                }
-            }
+        img
+    }
+}
+
+
+fn f16_to_f32(half: u16) -> f32 {
+    let sign = ((half >> 15) as u32) << 31;
+    let exponent = ((half >> 10) & 0x1f) as u32;
+    let mantissa = (half & 0x3ff) as u32;
+    let bits = match exponent {
+        0 if mantissa == 0 => sign,
+        0 => {
+            // subnormal
+            let value = mantissa as f32 / 1024.0 / 16384.0;
+            return if sign != 0 { -value } else { value }
+        },
+        0x1f => sign | 0x7f80_0000 | (mantissa << 13),
+        _ => sign | ((exponent + 127 - 15) << 23) | (mantissa << 13),
+    };
+    f32::from_bits(bits)
+}
+
+fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+    if exponent == 0xff {
+        return sign | 0x7c00 | if mantissa != 0 { 0x200 } else { 0 }
+    }
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        sign | 0x7c00
+    } else if exponent <= 0 {
+        if exponent < -10 {
+            return sign
         }
-        img_data
+        let mantissa = mantissa | 0x80_0000;
+        sign | (mantissa >> (14 - exponent)) as u16
+    } else {
+        sign | ((exponent as u16) << 10) | (mantissa >> 13) as u16
     }
 }
@@ -0,0 +1,158 @@
+use super::{Image, PixelData, PixelFormat};
+
+enum Mode {
+    Gray,
+    Bgr,
+    Bgra,
+}
+
+impl Image {
+    pub fn from_bmp(data: &[u8]) -> Result<Self, String> {
+        let (width, height, top_down, offset, mode) = Self::load_bmp_header(data)?;
+        let (img_data, format) = Self::load_bmp_data(data, width, height, top_down, offset, mode)?;
+        Self::from_raw(width, height, format, PixelData::U8(img_data))
+    }
+
+    fn read_u16(data: &[u8], offset: usize) -> usize {
+        u16::from_le_bytes([data[offset], data[offset + 1]]) as usize
+    }
+
+    fn read_u32(data: &[u8], offset: usize) -> usize {
+        u32::from_le_bytes([
+            data[offset], data[offset + 1], data[offset + 2], data[offset + 3]
+        ]) as usize
+    }
+
+    // A negative height is a top-down image, with its first row at the top.
+    fn load_bmp_header(data: &[u8]) -> Result<(usize, usize, bool, usize, Mode), String> {
+        // There should be 54bite in the header.
+        if data.len() < 54 || data[0] != b'B' || data[1] != b'M' {
+            return Err("error: not bmp".to_string())
+        }
+
+        // The pixel array follows the header, which is longer than 54bite
+        // for BITMAPV4/V5 files (the usual way BGRA images are written).
+        let offset = Self::read_u32(data, 0x0A);
+        let width = Self::read_u32(data, 0x12);
+        let signed_height = Self::read_u32(data, 0x16) as u32 as i32;
+        let top_down = signed_height < 0;
+        let height = signed_height.unsigned_abs() as usize;
+        let bit_count = Self::read_u16(data, 0x1C);
+        if offset < 54 || offset > data.len() {
+            return Err("error: not supported bmp: header_size".to_string())
+        }
+        // GL 3 handles any size, and cubemap crosses are 4:3 anyway.
+        if width == 0 || height == 0 {
+            return Err("error: not supported bmp: empty image".to_string())
+        }
+        let mode = match bit_count {
+            8 => Mode::Gray,
+            24 => Mode::Bgr,
+            32 => Mode::Bgra,
+            _ => return Err("error: not supported bmp: bit count".to_string()),
+        };
+        Ok((width, height, top_down, offset, mode))
+    }
+
+    fn load_bmp_data(data: &[u8], width: usize, height: usize, top_down: bool, offset: usize, mode: Mode)
+            -> Result<(Vec<u8>, PixelFormat), String> {
+        let bytes = match mode {
+            Mode::Gray => 1,
+            Mode::Bgr => 3,
+            Mode::Bgra => 4,
+        };
+        // Each row is padded to a multiple of 4 bytes. The sizes come from
+        // the header, so they may not fit in a usize.
+        let row_size = width.checked_mul(bytes)
+            .and_then(|size| size.checked_add(3))
+            .map(|size| size & !3);
+        let end = row_size.and_then(|size| size.checked_mul(height)).and_then(|size| size.checked_add(offset));
+        let (Some(row_size), Some(end)) = (row_size, end) else {
+            return Err("error: not supported bmp: image size".to_string())
+        };
+        if data.len() < end {
+            return Err("error: not supported bmp: data size".to_string())
+        }
+        // Image rows go up, like the bottom-up rows of the file.
+        let row_offset = |i: usize| offset + (if top_down { height - 1 - i } else { i }) * row_size;
+        // Plain 32bit BMPs often leave the fourth byte as zero padding,
+        // which would make the whole image invisible.
+        let keep_alpha = matches!(mode, Mode::Bgra) && (0..height).any(|i| {
+            (0..width).any(|j| data[row_offset(i) + j * 4 + 3] != 255)
+        }) && (0..height).any(|i| {
+            (0..width).any(|j| data[row_offset(i) + j * 4 + 3] != 0)
+        });
+        let format = match mode {
+            Mode::Gray => PixelFormat::R8,
+            Mode::Bgr => PixelFormat::RGB8,
+            Mode::Bgra if keep_alpha => PixelFormat::RGBA8,
+            Mode::Bgra => PixelFormat::RGB8,
+        };
+        let channels = format.channels();
+        let mut img_data = Vec::with_capacity(channels * width * height);
+        for i in 0..height {
+            for j in 0..width {
+                let data_offset = row_offset(i) + j * bytes;
+                match mode {
+                    Mode::Gray => img_data.push(data[data_offset]),
+                    Mode::Bgr | Mode::Bgra => {
+                        img_data.push(data[data_offset + 2]);
+                        img_data.push(data[data_offset + 1]);
+                        img_data.push(data[data_offset]);
+                        if keep_alpha {
+                            img_data.push(data[data_offset + 3]);
+                        }
+                    }
+                }
+            }
+        }
+        Ok((img_data, format))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A 24 bit BMP with a 54 byte header and the rows as given.
+    fn bmp(width: u32, height: i32, rows: &[&[u8]]) -> Vec<u8> {
+        let mut data = vec![0; 54];
+        data[0] = b'B';
+        data[1] = b'M';
+        data[0x0A..0x0E].copy_from_slice(&54u32.to_le_bytes());
+        data[0x12..0x16].copy_from_slice(&width.to_le_bytes());
+        data[0x16..0x1A].copy_from_slice(&height.to_le_bytes());
+        data[0x1C..0x1E].copy_from_slice(&24u16.to_le_bytes());
+        for row in rows {
+            data.extend_from_slice(row);
+        }
+        data
+    }
+
+    // One pixel per row, blue green red and a byte of padding.
+    const RED: [u8; 4] = [0, 0, 255, 0];
+    const BLUE: [u8; 4] = [255, 0, 0, 0];
+
+    #[test]
+    fn bottom_up_rows_start_at_the_bottom() {
+        let image = Image::from_bmp(&bmp(1, 2, &[&RED, &BLUE])).unwrap();
+        assert_eq!((image.get_width(), image.get_height()), (1, 2));
+        assert_eq!(image.get_pixel(0, 0), [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(image.get_pixel(0, 1), [0.0, 0.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn oversized_header_is_an_error() {
+        assert!(Image::from_bmp(&bmp(u32::MAX, i32::MAX, &[&RED])).is_err());
+        assert!(Image::from_bmp(&bmp(u32::MAX, i32::MIN, &[&RED])).is_err());
+        assert!(Image::from_bmp(&bmp(2, 2, &[&RED])).is_err());
+    }
+
+    #[test]
+    fn top_down_rows_start_at_the_top() {
+        let image = Image::from_bmp(&bmp(1, -2, &[&RED, &BLUE])).unwrap();
+        assert_eq!((image.get_width(), image.get_height()), (1, 2));
+        assert_eq!(image.get_pixel(0, 0), [0.0, 0.0, 1.0, 1.0]);
+        assert_eq!(image.get_pixel(0, 1), [1.0, 0.0, 0.0, 1.0]);
+    }
+}
@@ -0,0 +1,115 @@
+use super::{Image, PixelData, PixelFormat};
+
+// Radiance RGBE (.hdr) images, loaded as RGB32F in linear color.
+
+impl Image {
+    pub fn is_hdr(data: &[u8]) -> bool {
+        data.starts_with(b"#?")
+    }
+
+    pub fn from_hdr(data: &[u8]) -> Result<Self, String> {
+        let (width, height, top_down, offset) = Self::load_hdr_header(data)?;
+        let mut pixels = vec![0.0; width * height * 3];
+        let mut offset = offset;
+        let mut scanline = vec![0u8; width * 4];
+        for i in 0..height {
+            offset = Self::read_hdr_scanline(data, offset, &mut scanline)?;
+            // Images are stored from the bottom row.
+            let row = if top_down { height - 1 - i } else { i };
+            for j in 0..width {
+                let rgbe = &scanline[j * 4..j * 4 + 4];
+                let scale = if rgbe[3] == 0 { 0.0 } else { 2f32.powi(rgbe[3] as i32 - 136) };
+                let index = (row * width + j) * 3;
+                pixels[index] = rgbe[0] as f32 * scale;
+                pixels[index + 1] = rgbe[1] as f32 * scale;
+                pixels[index + 2] = rgbe[2] as f32 * scale;
+            }
+        }
+        Self::from_raw(width, height, PixelFormat::RGB32F, PixelData::F32(pixels))
+    }
+
+    fn load_hdr_header(data: &[u8]) -> Result<(usize, usize, bool, usize), String> {
+        let mut offset = 0;
+        let mut next_line = || -> Result<String, String> {
+            let end = data[offset..].iter().position(|b| *b == b'\n')
+                .ok_or_else(|| "error: not supported hdr: header".to_string())?;
+            let line = String::from_utf8_lossy(&data[offset..offset + end]).trim().to_string();
+            offset += end + 1;
+            Ok(line)
+        };
+        loop {
+            let line = next_line()?;
+            if line.is_empty() {
+                break;
+            }
+            if let Some(format) = line.strip_prefix("FORMAT=") {
+                if format != "32-bit_rle_rgbe" {
+                    return Err(format!("error: not supported hdr: format {}", format))
+                }
+            }
+        }
+        // Only the usual "-Y height +X width" and its bottom-up variant.
+        let resolution = next_line()?;
+        let fields: Vec<&str> = resolution.split_whitespace().collect();
+        let (top_down, height, width) = match fields.as_slice() {
+            ["-Y", h, "+X", w] => (true, h, w),
+            ["+Y", h, "+X", w] => (false, h, w),
+            _ => return Err(format!("error: not supported hdr: resolution {}", resolution)),
+        };
+        let error = || format!("error: not supported hdr: resolution {}", resolution);
+        let height: usize = height.parse().map_err(|_| error())?;
+        let width: usize = width.parse().map_err(|_| error())?;
+        if width == 0 || height == 0 {
+            return Err(error())
+        }
+        Ok((width, height, top_down, offset))
+    }
+
+    // Reads one scanline of RGBE bytes, run length encoded per channel or flat.
+    fn read_hdr_scanline(data: &[u8], offset: usize, scanline: &mut [u8]) -> Result<usize, String> {
+        let width = scanline.len() / 4;
+        let error = || "error: not supported hdr: data size".to_string();
+        let header = data.get(offset..offset + 4).ok_or_else(error)?;
+        let encoded = (8..0x8000).contains(&width) && header[0] == 2 && header[1] == 2 && header[2] & 0x80 == 0;
+        if !encoded {
+            let flat = data.get(offset..offset + width * 4).ok_or_else(error)?;
+            scanline.copy_from_slice(flat);
+            return Ok(offset + width * 4)
+        }
+        if ((header[2] as usize) << 8 | header[3] as usize) != width {
+            return Err("error: not supported hdr: scanline width".to_string())
+        }
+        let mut offset = offset + 4;
+        for channel in 0..4 {
+            let mut x = 0;
+            while x < width {
+                let count = *data.get(offset).ok_or_else(error)? as usize;
+                offset += 1;
+                if count > 128 {
+                    // A run of one value.
+                    let count = count - 128;
+                    let value = *data.get(offset).ok_or_else(error)?;
+                    offset += 1;
+                    if x + count > width {
+                        return Err(error())
+                    }
+                    for i in 0..count {
+                        scanline[(x + i) * 4 + channel] = value;
+                    }
+                    x += count;
+                } else {
+                    if count == 0 || x + count > width {
+                        return Err(error())
+                    }
+                    let values = data.get(offset..offset + count).ok_or_else(error)?;
+                    for (i, value) in values.iter().enumerate() {
+                        scanline[(x + i) * 4 + channel] = *value;
+                    }
+                    offset += count;
+                    x += count;
+                }
+            }
+        }
+        Ok(offset)
+    }
+}
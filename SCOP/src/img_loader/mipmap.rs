@@ -0,0 +1,242 @@
+use std::f32::consts::PI;
+use std::str::FromStr;
+
+use super::{ColorSpace, Image, PixelFormat};
+use super::ops::{srgb_to_linear, linear_to_srgb};
+
+
+const LANCZOS_RADIUS: f32 = 3.0;
+const KAISER_RADIUS: f32 = 3.0;
+const KAISER_ALPHA: f32 = 4.0;
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MipFilter {
+    Box,
+    Kaiser,
+    Lanczos,
+}
+
+impl FromStr for MipFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "box" => Ok(MipFilter::Box),
+            "kaiser" => Ok(MipFilter::Kaiser),
+            "lanczos" => Ok(MipFilter::Lanczos),
+            _ => Err(format!("error: unknown mip filter: {}", s)),
+        }
+    }
+}
+
+impl MipFilter {
+    fn radius(&self) -> f32 {
+        match self {
+            MipFilter::Box => 0.5,
+            MipFilter::Kaiser => KAISER_RADIUS,
+            MipFilter::Lanczos => LANCZOS_RADIUS,
+        }
+    }
+
+    fn weight(&self, x: f32) -> f32 {
+        let x = x.abs();
+        match self {
+            MipFilter::Box => if x <= 0.5 { 1.0 } else { 0.0 },
+            MipFilter::Kaiser => {
+                if x >= KAISER_RADIUS {
+                    return 0.0
+                }
+                let t = x / KAISER_RADIUS;
+                sinc(x) * bessel_i0(KAISER_ALPHA * (1.0 - t * t).sqrt()) / bessel_i0(KAISER_ALPHA)
+            },
+            MipFilter::Lanczos => {
+                if x >= LANCZOS_RADIUS {
+                    return 0.0
+                }
+                sinc(x) * sinc(x / LANCZOS_RADIUS)
+            },
+        }
+    }
+}
+
+fn sinc(x: f32) -> f32 {
+    if x.abs() < 1e-6 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+fn bessel_i0(x: f32) -> f32 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let half = x / 2.0;
+    for k in 1..20 {
+        term *= half / k as f32;
+        sum += term * term;
+    }
+    sum
+}
+
+impl ColorSpace {
+    // The normalized integer formats usually hold gamma encoded colors, the
+    // float formats linear ones.
+    pub fn default_for(format: PixelFormat) -> Self {
+        if format.is_float() { ColorSpace::Linear } else { ColorSpace::Srgb }
+    }
+}
+
+impl Image {
+    // Returns every level below this image down to 1x1.
+    pub fn mip_chain(&self, filter: MipFilter, wrap: bool) -> Vec<Image> {
+        let gamma = self.color_space == ColorSpace::Srgb;
+        let mut levels = Vec::new();
+        let mut width = self.width;
+        let mut height = self.height;
+        let mut pixels = self.to_linear_pixels(gamma);
+        while width > 1 || height > 1 {
+            let new_width = (width / 2).max(1);
+            let new_height = (height / 2).max(1);
+            let horizontal = resample_axis(&pixels, width, height, new_width, true, filter, wrap);
+            pixels = resample_axis(&horizontal, new_width, height, new_height, false, filter, wrap);
+            width = new_width;
+            height = new_height;
+            levels.push(Self::from_linear_pixels(&pixels, width, height, self.format, gamma).with_color_space(self.color_space));
+        }
+        levels
+    }
+
+    fn to_linear_pixels(&self, gamma: bool) -> Vec<[f32; 4]> {
+        let mut pixels = Vec::with_capacity(self.width * self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let mut p = self.get_pixel(x, y);
+                if gamma {
+                    for v in p.iter_mut().take(3) {
+                        *v = srgb_to_linear(*v);
+                    }
+                }
+                pixels.push(p);
+            }
+        }
+        pixels
+    }
+
+    fn from_linear_pixels(pixels: &[[f32; 4]], width: usize, height: usize, format: PixelFormat, gamma: bool) -> Self {
+        let mut img = Self::blank(width, height, format);
+        for y in 0..height {
+            for x in 0..width {
+                let mut p = pixels[y * width + x];
+                if gamma {
+                    for v in p.iter_mut().take(3) {
+                        *v = linear_to_srgb(v.max(0.0));
+                    }
+                }
+                img.set_pixel(x, y, p);
+            }
+        }
+        img
+    }
+}
+
+// Resamples one axis from `size` to `new_size` texels with a separable kernel
+// stretched over the source footprint of each destination texel.
+fn resample_axis(
+    pixels: &[[f32; 4]],
+    width: usize,
+    height: usize,
+    new_size: usize,
+    horizontal: bool,
+    filter: MipFilter,
+    wrap: bool,
+) -> Vec<[f32; 4]> {
+    let (size, other) = if horizontal { (width, height) } else { (height, width) };
+    let scale = size as f32 / new_size as f32;
+    let support = filter.radius() * scale;
+    let mut taps = Vec::with_capacity(new_size);
+    for i in 0..new_size {
+        let center = (i as f32 + 0.5) * scale;
+        let start = (center - support).floor() as i64;
+        let end = (center + support).ceil() as i64;
+        let mut weights = Vec::new();
+        let mut total = 0.0;
+        for s in start..end {
+            let w = filter.weight((s as f32 + 0.5 - center) / scale);
+            if w != 0.0 {
+                let index = if wrap {
+                    s.rem_euclid(size as i64)
+                } else {
+                    s.clamp(0, size as i64 - 1)
+                } as usize;
+                weights.push((index, w));
+                total += w;
+            }
+        }
+        for w in weights.iter_mut() {
+            w.1 /= total;
+        }
+        taps.push(weights);
+    }
+
+    let (new_width, new_height) = if horizontal { (new_size, height) } else { (width, new_size) };
+    let mut out = vec![[0.0; 4]; new_width * new_height];
+    for o in 0..other {
+        for (i, weights) in taps.iter().enumerate() {
+            let mut sum = [0.0; 4];
+            for (index, w) in weights {
+                let p = if horizontal {
+                    pixels[o * width + index]
+                } else {
+                    pixels[index * width + o]
+                };
+                for c in 0..4 {
+                    sum[c] += p[c] * w;
+                }
+            }
+            // Negative lobes can overshoot.
+            sum[3] = sum[3].clamp(0.0, 1.0);
+            if horizontal {
+                out[o * new_width + i] = sum;
+            } else {
+                out[i * new_width + o] = sum;
+            }
+        }
+    }
+    out
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // One black and one white texel, averaged into a single one.
+    fn black_and_white() -> Image {
+        let mut image = Image::blank(2, 1, PixelFormat::RGB8);
+        image.set_pixel(1, 0, [1.0, 1.0, 1.0, 1.0]);
+        image
+    }
+
+    #[test]
+    fn srgb_levels_average_the_light() {
+        let levels = black_and_white().mip_chain(MipFilter::Box, false);
+        assert_eq!(levels.len(), 1);
+        assert_eq!(levels[0].color_space(), ColorSpace::Srgb);
+        assert!((levels[0].get_pixel(0, 0)[0] - linear_to_srgb(0.5)).abs() < 1.0 / 255.0);
+    }
+
+    #[test]
+    fn linear_levels_average_the_values() {
+        let image = black_and_white().with_color_space(ColorSpace::Linear);
+        let levels = image.mip_chain(MipFilter::Box, false);
+        assert_eq!(levels[0].color_space(), ColorSpace::Linear);
+        assert!((levels[0].get_pixel(0, 0)[0] - 0.5).abs() < 1.0 / 255.0);
+    }
+
+    #[test]
+    fn float_images_default_to_linear() {
+        assert_eq!(Image::blank(1, 1, PixelFormat::RGBA32F).color_space(), ColorSpace::Linear);
+        assert_eq!(Image::blank(1, 1, PixelFormat::RGBA8).color_space(), ColorSpace::Srgb);
+    }
+}
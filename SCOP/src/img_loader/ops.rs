@@ -0,0 +1,328 @@
+use std::str::FromStr;
+
+use super::{ColorSpace, Image};
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResizeFilter {
+    Bilinear,
+    Bicubic,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImageOp {
+    FlipVertical,
+    FlipHorizontal,
+    Resize(usize, usize, ResizeFilter),
+    Crop(usize, usize, usize, usize),
+    Swizzle(String),
+    SrgbToLinear,
+    LinearToSrgb,
+}
+
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+impl FromStr for ResizeFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bilinear" => Ok(ResizeFilter::Bilinear),
+            "bicubic" => Ok(ResizeFilter::Bicubic),
+            _ => Err(format!("error: unknown resize filter: {}", s)),
+        }
+    }
+}
+
+impl ImageOp {
+    // "WxH" or "WxH:bicubic"
+    pub fn parse_resize(s: &str) -> Result<Self, String> {
+        let (size, filter) = match s.split_once(':') {
+            Some((size, filter)) => (size, filter.parse()?),
+            None => (s, ResizeFilter::Bilinear),
+        };
+        let (width, height) = size.split_once('x')
+            .ok_or_else(|| format!("error: resize: {}", s))?;
+        let width = width.parse().map_err(|_| format!("error: resize: {}", s))?;
+        let height = height.parse().map_err(|_| format!("error: resize: {}", s))?;
+        Ok(ImageOp::Resize(width, height, filter))
+    }
+
+    // "x,y,w,h"
+    pub fn parse_crop(s: &str) -> Result<Self, String> {
+        let values = s.split(',')
+            .map(|v| v.parse::<usize>().map_err(|_| format!("error: crop: {}", s)))
+            .collect::<Result<Vec<_>, _>>()?;
+        if values.len() != 4 {
+            return Err(format!("error: crop: {}", s))
+        }
+        Ok(ImageOp::Crop(values[0], values[1], values[2], values[3]))
+    }
+
+    pub fn parse_swizzle(s: &str) -> Result<Self, String> {
+        if !(3..=4).contains(&s.len()) || !s.chars().all(|c| "rgba01".contains(c)) {
+            return Err(format!("error: swizzle: {}", s))
+        }
+        Ok(ImageOp::Swizzle(s.to_string()))
+    }
+
+    pub fn apply(&self, image: &Image) -> Result<Image, String> {
+        match self {
+            ImageOp::FlipVertical => Ok(image.flip_vertical()),
+            ImageOp::FlipHorizontal => Ok(image.flip_horizontal()),
+            ImageOp::Resize(width, height, filter) => image.resize(*width, *height, *filter),
+            ImageOp::Crop(x, y, width, height) => image.crop(*x, *y, *width, *height),
+            ImageOp::Swizzle(pattern) => image.swizzle(pattern),
+            ImageOp::SrgbToLinear => Ok(image.map_color(srgb_to_linear).with_color_space(ColorSpace::Linear)),
+            ImageOp::LinearToSrgb => Ok(image.map_color(linear_to_srgb).with_color_space(ColorSpace::Srgb)),
+        }
+    }
+}
+
+impl Image {
+    pub fn apply_ops(self, ops: &[ImageOp]) -> Result<Self, String> {
+        let mut image = self;
+        for op in ops {
+            image = op.apply(&image)?;
+        }
+        Ok(image)
+    }
+
+    fn map_pixels<F>(&self, width: usize, height: usize, f: F) -> Self
+    where
+        F: Fn(usize, usize) -> [f32; 4],
+    {
+        let mut img = Self::blank(width, height, self.format).with_color_space(self.color_space);
+        for y in 0..height {
+            for x in 0..width {
+                img.set_pixel(x, y, f(x, y));
+            }
+        }
+        img
+    }
+
+    pub fn flip_vertical(&self) -> Self {
+        self.map_pixels(self.width, self.height, |x, y| self.get_pixel(x, self.height - 1 - y))
+    }
+
+    pub fn flip_horizontal(&self) -> Self {
+        self.map_pixels(self.width, self.height, |x, y| self.get_pixel(self.width - 1 - x, y))
+    }
+
+    pub fn crop(&self, x: usize, y: usize, width: usize, height: usize) -> Result<Self, String> {
+        let outside = |start: usize, size: usize, limit: usize| start.checked_add(size).map_or(true, |end| end > limit);
+        if width == 0 || height == 0 || outside(x, width, self.width) || outside(y, height, self.height) {
+            return Err(format!("error: crop {},{},{},{} is out of {}x{}",
+                x, y, width, height, self.width, self.height))
+        }
+        Ok(self.map_pixels(width, height, |i, j| self.get_pixel(x + i, y + j)))
+    }
+
+    pub fn swizzle(&self, pattern: &str) -> Result<Self, String> {
+        let pattern = ImageOp::parse_swizzle(pattern).map(|_| pattern.as_bytes())?;
+        Ok(self.map_pixels(self.width, self.height, |x, y| {
+            let p = self.get_pixel(x, y);
+            let mut out = [0.0, 0.0, 0.0, p[3]];
+            for (o, c) in out.iter_mut().zip(pattern.iter()) {
+                *o = match c {
+                    b'r' => p[0],
+                    b'g' => p[1],
+                    b'b' => p[2],
+                    b'a' => p[3],
+                    b'0' => 0.0,
+                    _ => 1.0,
+                };
+            }
+            out
+        }))
+    }
+
+    // Applies `f` to the color channels, leaving alpha as is.
+    pub fn map_color<F>(&self, f: F) -> Self
+    where
+        F: Fn(f32) -> f32,
+    {
+        self.map_pixels(self.width, self.height, |x, y| {
+            let p = self.get_pixel(x, y);
+            [f(p[0]), f(p[1]), f(p[2]), p[3]]
+        })
+    }
+
+    pub fn resize(&self, width: usize, height: usize, filter: ResizeFilter) -> Result<Self, String> {
+        if width == 0 || height == 0 {
+            return Err(format!("error: resize to {}x{}", width, height))
+        }
+        let scale_x = self.width as f32 / width as f32;
+        let scale_y = self.height as f32 / height as f32;
+        Ok(self.map_pixels(width, height, |x, y| {
+            let sx = (x as f32 + 0.5) * scale_x - 0.5;
+            let sy = (y as f32 + 0.5) * scale_y - 0.5;
+            match filter {
+                ResizeFilter::Bilinear => self.sample_bilinear(sx, sy),
+                ResizeFilter::Bicubic => self.sample_bicubic(sx, sy),
+            }
+        }))
+    }
+
+    fn get_pixel_clamped(&self, x: i64, y: i64) -> [f32; 4] {
+        self.get_pixel(
+            x.clamp(0, self.width as i64 - 1) as usize,
+            y.clamp(0, self.height as i64 - 1) as usize,
+        )
+    }
+
+    pub fn sample_bilinear(&self, x: f32, y: f32) -> [f32; 4] {
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let tx = x - x0;
+        let ty = y - y0;
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        let mut out = [0.0; 4];
+        for (dy, wy) in [(0, 1.0 - ty), (1, ty)] {
+            for (dx, wx) in [(0, 1.0 - tx), (1, tx)] {
+                let p = self.get_pixel_clamped(x0 + dx, y0 + dy);
+                for c in 0..4 {
+                    out[c] += p[c] * wx * wy;
+                }
+            }
+        }
+        out
+    }
+
+    fn sample_bicubic(&self, x: f32, y: f32) -> [f32; 4] {
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let wx = catmull_rom_weights(x - x0);
+        let wy = catmull_rom_weights(y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        let mut out = [0.0; 4];
+        for (j, wy) in wy.iter().enumerate() {
+            for (i, wx) in wx.iter().enumerate() {
+                let p = self.get_pixel_clamped(x0 + i as i64 - 1, y0 + j as i64 - 1);
+                for c in 0..4 {
+                    out[c] += p[c] * wx * wy;
+                }
+            }
+        }
+        out
+    }
+}
+
+fn catmull_rom_weights(t: f32) -> [f32; 4] {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    [
+        -0.5 * t3 + t2 - 0.5 * t,
+        1.5 * t3 - 2.5 * t2 + 1.0,
+        -1.5 * t3 + 2.0 * t2 + 0.5 * t,
+        0.5 * t3 - 0.5 * t2,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::img_loader::PixelFormat;
+
+    // Each pixel holds its own coordinates, in red and green.
+    fn numbered(width: usize, height: usize) -> Image {
+        let mut image = Image::blank(width, height, PixelFormat::RGBA32F);
+        for y in 0..height {
+            for x in 0..width {
+                image.set_pixel(x, y, [x as f32, y as f32, 0.5, 0.25]);
+            }
+        }
+        image
+    }
+
+    fn size(image: &Image) -> (usize, usize) {
+        (image.get_width(), image.get_height())
+    }
+
+    #[test]
+    fn flip_vertical_reverses_rows() {
+        let image = numbered(3, 2).flip_vertical();
+        assert_eq!(size(&image), (3, 2));
+        assert_eq!(image.get_pixel(0, 0), [0.0, 1.0, 0.5, 0.25]);
+        assert_eq!(image.get_pixel(2, 1), [2.0, 0.0, 0.5, 0.25]);
+    }
+
+    #[test]
+    fn flip_horizontal_reverses_columns() {
+        let image = numbered(3, 2).flip_horizontal();
+        assert_eq!(size(&image), (3, 2));
+        assert_eq!(image.get_pixel(0, 0), [2.0, 0.0, 0.5, 0.25]);
+        assert_eq!(image.get_pixel(2, 1), [0.0, 1.0, 0.5, 0.25]);
+    }
+
+    #[test]
+    fn crop_keeps_the_rectangle() {
+        let image = numbered(4, 3).crop(1, 1, 2, 2).unwrap();
+        assert_eq!(size(&image), (2, 2));
+        assert_eq!(image.get_pixel(0, 0), [1.0, 1.0, 0.5, 0.25]);
+        assert_eq!(image.get_pixel(1, 1), [2.0, 2.0, 0.5, 0.25]);
+        assert!(numbered(4, 3).crop(3, 0, 2, 1).is_err());
+        assert!(numbered(4, 3).crop(0, 0, 0, 1).is_err());
+        assert!(numbered(4, 3).crop(usize::MAX, 0, 2, 1).is_err());
+        assert!(numbered(4, 3).crop(0, 1, 1, usize::MAX).is_err());
+    }
+
+    #[test]
+    fn resize_keeps_the_orientation() {
+        for filter in [ResizeFilter::Bilinear, ResizeFilter::Bicubic] {
+            let image = numbered(4, 2).resize(8, 6, filter).unwrap();
+            assert_eq!(size(&image), (8, 6));
+            let (first, last) = (image.get_pixel(0, 0), image.get_pixel(7, 5));
+            assert!(first[0] < last[0] && first[1] < last[1]);
+            let image = numbered(4, 2).resize(2, 1, filter).unwrap();
+            assert_eq!(size(&image), (2, 1));
+            assert!(image.get_pixel(0, 0)[0] < image.get_pixel(1, 0)[0]);
+        }
+        assert!(numbered(4, 2).resize(0, 2, ResizeFilter::Bilinear).is_err());
+    }
+
+    #[test]
+    fn swizzle_reorders_channels() {
+        let image = numbered(2, 2).swizzle("bgr").unwrap();
+        assert_eq!(size(&image), (2, 2));
+        assert_eq!(image.get_pixel(1, 0), [0.5, 0.0, 1.0, 0.25]);
+        let image = numbered(2, 2).swizzle("rg01").unwrap();
+        assert_eq!(image.get_pixel(1, 1), [1.0, 1.0, 0.0, 1.0]);
+        assert!(numbered(2, 2).swizzle("rgx").is_err());
+    }
+
+    #[test]
+    fn srgb_conversions_invert_each_other_and_keep_alpha() {
+        let image = numbered(2, 1).map_color(|_| 0.5);
+        let linear = ImageOp::SrgbToLinear.apply(&image).unwrap();
+        assert_eq!(size(&linear), (2, 1));
+        let p = linear.get_pixel(0, 0);
+        assert!((p[0] - 0.214).abs() < 1e-3);
+        assert_eq!(p[3], 0.25);
+        let back = ImageOp::LinearToSrgb.apply(&linear).unwrap().get_pixel(0, 0);
+        assert!((back[0] - 0.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn ops_apply_in_order() {
+        let ops = [ImageOp::parse_crop("1,0,2,2").unwrap(), ImageOp::FlipHorizontal];
+        let image = numbered(4, 2).apply_ops(&ops).unwrap();
+        assert_eq!(size(&image), (2, 2));
+        assert_eq!(image.get_pixel(0, 0), [2.0, 0.0, 0.5, 0.25]);
+        assert_eq!(ImageOp::parse_resize("8x4:bicubic"), Ok(ImageOp::Resize(8, 4, ResizeFilter::Bicubic)));
+    }
+}
@@ -0,0 +1,159 @@
+use std::ffi::CString;
+
+use crate::mat::Vec3d;
+use crate::texture::cubemap::Cubemap;
+
+
+// Must match MAX_POINT_LIGHTS in triangle.frag.
+const MAX_POINT_LIGHTS: usize = 4;
+const KEY_LIGHT_STEP: f32 = std::f32::consts::PI / 18.0;
+const KEY_LIGHT_MAX_PITCH: f32 = std::f32::consts::FRAC_PI_2 - 0.01;
+
+
+// Light coming from the environment map, used as the ambient color. Its
+// color is scaled to a brightest channel of 1.
+#[derive(Debug)]
+pub struct EnvironmentLight {
+    color: [f32; 3],
+    id: i32,
+}
+
+// Lights are in camera space: the camera is at the origin looking at +Z,
+// so they stay in place while the model turns.
+#[derive(Debug)]
+pub struct DirectionalLight {
+    yaw: f32,
+    pitch: f32,
+    color: [f32; 3],
+}
+
+#[derive(Debug)]
+pub struct PointLight {
+    position: Vec3d,
+    color: [f32; 3],
+}
+
+#[derive(Debug)]
+pub struct Lighting {
+    on: bool,
+    environment: EnvironmentLight,
+    key: DirectionalLight,
+    points: Vec<PointLight>,
+    ids: LightingIds,
+}
+
+#[derive(Debug, Default)]
+struct LightingIds {
+    is_lighting: i32,
+    key_direction: i32,
+    key_color: i32,
+    point_count: i32,
+    point_position: i32,
+    point_color: i32,
+}
+
+impl EnvironmentLight {
+    pub fn new() -> Self {
+        Self { color: [1.0, 1.0, 1.0], id: 0 }
+    }
+
+    pub fn from_cubemap(cubemap: &Cubemap) -> Self {
+        let radiance = cubemap.average_radiance();
+        let max = radiance.iter().cloned().fold(0.0, f32::max);
+        let color = if max > 0.0 {
+            radiance.map(|v| v / max)
+        } else {
+            [1.0, 1.0, 1.0]
+        };
+        Self { color, id: 0 }
+    }
+
+    pub fn set_program(&mut self, program_id: gl::types::GLuint) -> Result<(), String> {
+        self.id = get_uniform_location(program_id, "Ambient")?;
+        Ok(())
+    }
+
+    pub fn set(&self) {
+        unsafe {
+            gl::Uniform3f(self.id, self.color[0], self.color[1], self.color[2]);
+        }
+    }
+}
+
+impl DirectionalLight {
+    // Direction towards the light.
+    pub fn direction(&self) -> Vec3d {
+        Vec3d::new(
+            self.yaw.sin() * self.pitch.cos(),
+            self.pitch.sin(),
+            -self.yaw.cos() * self.pitch.cos(),
+        )
+    }
+}
+
+impl Lighting {
+    pub fn new(environment: EnvironmentLight) -> Self {
+        Self {
+            on: true,
+            environment,
+            // Upper left, from the camera side.
+            key: DirectionalLight { yaw: -0.5, pitch: 0.6, color: [0.9, 0.9, 0.85] },
+            points: vec![
+                PointLight { position: Vec3d::new(1.5, 0.5, 0.5), color: [0.5, 0.4, 0.3] },
+                PointLight { position: Vec3d::new(-1.5, -1.0, 1.0), color: [0.2, 0.25, 0.4] },
+                PointLight { position: Vec3d::new(0.0, 1.5, 4.0), color: [0.4, 0.4, 0.4] },
+            ],
+            ids: LightingIds::default(),
+        }
+    }
+
+    pub fn set_program(&mut self, program_id: gl::types::GLuint) -> Result<(), String> {
+        self.environment.set_program(program_id)?;
+        self.ids = LightingIds {
+            is_lighting: get_uniform_location(program_id, "IsLighting")?,
+            key_direction: get_uniform_location(program_id, "KeyLightDirection")?,
+            key_color: get_uniform_location(program_id, "KeyLightColor")?,
+            point_count: get_uniform_location(program_id, "PointLightCount")?,
+            point_position: get_uniform_location(program_id, "PointLightPosition")?,
+            point_color: get_uniform_location(program_id, "PointLightColor")?,
+        };
+        Ok(())
+    }
+
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+
+    pub fn switch(&mut self) {
+        self.on = !self.on;
+    }
+
+    pub fn move_key_light(&mut self, yaw: i32, pitch: i32) {
+        self.key.yaw += yaw as f32 * KEY_LIGHT_STEP;
+        self.key.pitch = (self.key.pitch + pitch as f32 * KEY_LIGHT_STEP)
+            .clamp(-KEY_LIGHT_MAX_PITCH, KEY_LIGHT_MAX_PITCH);
+    }
+
+    pub fn set(&self) {
+        self.environment.set();
+        let direction = self.key.direction();
+        let count = self.points.len().min(MAX_POINT_LIGHTS);
+        let positions: Vec<f32> = self.points[..count].iter()
+            .flat_map(|p| [p.position.d0, p.position.d1, p.position.d2])
+            .collect();
+        let colors: Vec<f32> = self.points[..count].iter().flat_map(|p| p.color).collect();
+        unsafe {
+            gl::Uniform1i(self.ids.is_lighting, self.on as i32);
+            gl::Uniform3f(self.ids.key_direction, direction.d0, direction.d1, direction.d2);
+            gl::Uniform3f(self.ids.key_color, self.key.color[0], self.key.color[1], self.key.color[2]);
+            gl::Uniform1i(self.ids.point_count, count as i32);
+            gl::Uniform3fv(self.ids.point_position, count as i32, positions.as_ptr());
+            gl::Uniform3fv(self.ids.point_color, count as i32, colors.as_ptr());
+        }
+    }
+}
+
+fn get_uniform_location(program_id: gl::types::GLuint, name: &str) -> Result<i32, String> {
+    let name = CString::new(name).map_err(|_| "error: GetUniformLocation".to_string())?;
+    Ok(unsafe { gl::GetUniformLocation(program_id, name.as_ptr()) })
+}
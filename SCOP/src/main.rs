@@ -11,12 +11,38 @@ mod shader;
 mod mvp;
 mod img_loader;
 mod texture;
+mod material;
+mod args;
+mod font;
+mod resource;
+mod gl_object;
+mod skybox;
+mod light;
+mod bbox;
+mod text_overlay;
+mod render_mode;
+mod scene;
+mod culling;
+mod normal_map;
 
 use loader::Loader;
 use model::Model;
 use shader::Program;
 use mvp::MVP;
-use texture::Texture;
+use texture::manager::TextureManager;
+use texture::cubemap::Cubemap;
+use skybox::Skybox;
+use light::{EnvironmentLight, Lighting};
+use bbox::BoundingBoxOverlay;
+use text_overlay::TextOverlay;
+use render_mode::Rendering;
+use scene::{Scene, SceneObject};
+use culling::Culling;
+use normal_map::NormalMap;
+use material::{Material, AlphaMode};
+use args::Args;
+
+const CLEAR_COLOR: [f32; 3] = [0.3, 0.3, 0.3];
 
 fn main() {
     if let Err(e) = run() {
@@ -24,46 +50,82 @@ fn main() {
     }
 }
 
-fn run() -> Result<(), String>{
-    let mut model = Model::new();
-    let mut texture;
+fn update_title(window: &mut sdl2::video::Window, textures: &TextureManager, scene: &Scene) {
+    let object = scene.selected().or_else(|| scene.objects().first());
+    let texture = match object {
+        Some(object) if object.model.is_texture_on() => {
+            format!("{} ({}/{})", textures.name(object.texture), object.texture + 1, textures.len())
+        },
+        _ => "color".to_string(),
+    };
+    let title = if scene.len() > 1 {
+        format!("scop - {} - {}", scene.selection_name(), texture)
+    } else {
+        format!("scop - {}", texture)
+    };
+    let _ = window.set_title(&title);
+}
 
-    let args: Vec<String> = env::args().collect();
-    if args.len() == 2 {
-        let loader = Loader::new(args[1].clone());
+fn load_model(path: Option<&str>, args: &Args) -> Result<Model, String> {
+    let mut model = Model::new();
+    if let Some(path) = path {
+        let loader = Loader::new(path.to_string());
         loader.parse(&mut model)?;
-        model.resolve_duplicate_indices();
-        // model.set_colors_one(1.0);
-        // model.set_colors_gradation();
-        // model.set_colors_gradation_colorful();
-        // model.set_colors_grain();
-        model.set_colors_gray(6);
-        texture = Texture::from_bmp_file("./scop/asserts/textures/sweets_shiroi_taiyaki_white.bmp")?;
-        // texture = Texture::red_gradation();
     } else {
-        // model.set_cube_sample();
-        // model.set_rect_sample();
-        // model.set_rect_uv_sample();
         model.set_cube_sample_uv();
-        model.resolve_duplicate_indices();
-        // model.set_colors_grain();
-        // model.set_colors_gradation();
-        model.set_colors_gray(6);
-        // texture = Texture::from_bmp_file("./scop/asserts/textures/sweets_shiroi_taiyaki_white.bmp")?;
-        texture = Texture::red_gradation();
     }
+    if args.repair {
+        for line in model.repair(args.repair_holes).lines() {
+            println!("{}", line);
+        }
+    }
+    if let Some(output) = &args.write_obj {
+        model.write_obj(output)?;
+        println!("written: {}", output);
+    }
+    // Before the corners are split, so the duplicates are the ones of the file.
+    let stats = model.update_stats();
+    if args.stats {
+        if let Some(path) = path {
+            println!("{}:", path);
+        }
+        for line in stats.lines() {
+            println!("{}", line);
+        }
+    }
+    model.resolve_duplicate_indices();
+    model.set_colors_gray(6);
+    model.set_normals(args.normal_mode, args.crease_angle);
+    Ok(model)
+}
+
+fn run() -> Result<(), String>{
+    let args: Vec<String> = env::args().collect();
+    let args = Args::parse(&args)?;
+    let mut models = Vec::new();
+    if args.model_paths.is_empty() {
+        models.push(("cube".to_string(), load_model(None, &args)?));
+    }
+    for path in &args.model_paths {
+        models.push((resource::file_stem(std::path::Path::new(path)), load_model(Some(path), &args)?));
+    }
+    let textures = TextureManager::load(&args)?;
+    let sky = match &args.skybox {
+        Some(spec) => Some(Cubemap::load(spec, args.model_path())?),
+        None => None,
+    };
 
     let sdl = sdl2::init()?;
     let video_subsystem = sdl.video()?;
 
     let gl_attr = video_subsystem.gl_attr();
     gl_attr.set_context_profile(sdl2::video::GLProfile::Core);
-    gl_attr.set_context_version(3, 2);
+    gl_attr.set_context_version(3, 3);
 
     let window_size = (900, 700);
 
-    let window = video_subsystem
-        .window("Test", window_size.0, window_size.1)
+    let mut window = video_subsystem
+        .window("scop", window_size.0, window_size.1)
         .opengl()
         .resizable()
         .build()
@@ -73,18 +135,61 @@ fn run() -> Result<(), String>{
     let _gl =
         gl::load_with(|s| video_subsystem.gl_get_proc_address(s) as *const std::os::raw::c_void);
 
-    let mut vao: gl::types::GLuint = 0;
-    unsafe {
-        gl::GenVertexArrays(1, &mut vao);
-        gl::BindVertexArray(vao);
-    }
+    // The GL objects owned by the models and the textures are deleted when
+    // `display` returns, while the context is still alive.
+    display(&sdl, &mut window, window_size, models, textures, sky, &args)
+}
 
-    model.set_uv();
+// Simplified and uploaded, once there is a GL context.
+fn prepare_model(name: &str, mut model: Model, args: &Args) -> Model {
+    model.set_uv_projection(args.uv_projection);
+    if args.simplify.is_some() || args.simplify_error.is_some() {
+        let before = model.triangle_count();
+        model = model.simplify(args.simplify.unwrap_or(0), args.simplify_error);
+        println!("{}: simplify: {} -> {} triangles", name, before, model.triangle_count());
+    }
+    model.build_lods(args.lod_levels);
+    if model.lod_count() > 1 {
+        println!("{}: lod: {} levels", name, model.lod_count());
+    }
+    model.set_weld_epsilon(args.weld_epsilon);
     model.set_buffers();
+    if let Some(stats) = model.buffer_stats() {
+        println!(
+            "{}: mesh: {} corners welded to {} vertices, {} triangles, ACMR {:.3} -> {:.3}",
+            name, stats.corners, stats.vertices, stats.triangles, stats.acmr_before, stats.acmr_after
+        );
+    }
     model.config_buffer();
+    model.set_subdivision(args.subdivision_scheme, args.subdivision_crease);
+    if args.subdivide > 0 {
+        model.set_subdivision_level(args.subdivide);
+        println!(
+            "{}: subdivision: level {}, {} triangles",
+            name, model.subdivision_level(), model.drawn_triangle_count()
+        );
+    }
+    let (min, max) = model.bounding_box();
+    let (sphere_center, bounding_radius) = model.bounding_sphere();
+    println!(
+        "{}: bounds: box {:?} - {:?}, sphere center {:?} radius {}",
+        name, [min.d0, min.d1, min.d2], [max.d0, max.d1, max.d2],
+        [sphere_center.d0, sphere_center.d1, sphere_center.d2], bounding_radius
+    );
+    model
+}
 
+fn display(
+    sdl: &sdl2::Sdl,
+    window: &mut sdl2::video::Window,
+    window_size: (u32, u32),
+    models: Vec<(String, Model)>,
+    mut textures: TextureManager,
+    sky: Option<Cubemap>,
+    args: &Args,
+) -> Result<(), String> {
     unsafe {
-        gl::ClearColor(0.3, 0.3, 0.3, 1.0);
+        gl::ClearColor(CLEAR_COLOR[0], CLEAR_COLOR[1], CLEAR_COLOR[2], 1.0);
         gl::Enable(gl::DEPTH_TEST);
         gl::Enable(gl::TEXTURE_2D);
         gl::DepthFunc(gl::LESS)
@@ -95,16 +200,58 @@ fn run() -> Result<(), String>{
         &CString::new(include_str!("../asserts/shaders/triangle.frag")).map_err(|_| "error: fragment shader".to_string())?
     )?;
 
-    let mut mvp = MVP::new(
-        program.id(),
-        model.get_max_size(),
-        model.get_vertices(),
-        window_size
-    )?;
+    let mut scene = Scene::new(program.id())?;
+    for (i, (name, model)) in models.into_iter().enumerate() {
+        // Of the mesh as loaded, before any simplification.
+        let stats = model.stats().cloned();
+        let mut model = prepare_model(&name, model, args);
+        model.set_texture_fade_ms(args.fade_ms);
+        let texture = if i < args.texture_paths.len() && i < textures.len() { i } else { 0 };
+        let material = Material::new(
+            if textures.has_alpha(texture) { AlphaMode::Mask } else { AlphaMode::Opaque }
+        );
+        scene.add(SceneObject::new(name, model, stats, material, texture))?;
+    }
+    for (child, parent) in &args.parents {
+        scene.set_parent(*child, *parent)?;
+    }
+    scene.layout();
+    if scene.len() > 1 {
+        for (i, object) in scene.objects().iter().enumerate() {
+            match object.parent() {
+                Some(parent) => println!("object {}: {} (child of {})", i + 1, object.name, parent + 1),
+                None => println!("object {}: {}", i + 1, object.name),
+            }
+        }
+    }
 
-    model.set_texture(program.id())?;
+    let (center, radius) = scene.bounds();
+    let mut mvp = MVP::new(program.id(), &center, radius, window_size)?;
+    let mut bbox = BoundingBoxOverlay::new(&mvp)?;
+    let mut stats_overlay = TextOverlay::new(&scene.stats_lines())?;
 
-    texture.set_texture();
+    let mut lighting = Lighting::new(match &sky {
+        Some(cubemap) => EnvironmentLight::from_cubemap(cubemap),
+        None => EnvironmentLight::new(),
+    });
+    lighting.set_program(program.id())?;
+
+    let mut skybox = match sky {
+        Some(cubemap) => Some(Skybox::new(cubemap, &mvp)?),
+        None => None,
+    };
+
+    textures.set_textures(&args.texture_options);
+    let mut normal_map = NormalMap::load(args.normal_map.as_deref(), args.model_path())?;
+    normal_map.set_texture(&args.texture_options);
+    normal_map.set_program(program.id())?;
+    update_title(window, &textures, &scene);
+
+    let mut rendering = Rendering::new(CLEAR_COLOR);
+    rendering.set_program(program.id())?;
+
+    let mut culling = Culling::new(args.cull, args.front_face);
+    culling.set_program(program.id())?;
 
     let mut before_timestamp = time::Instant::now();
 
@@ -126,19 +273,28 @@ fn run() -> Result<(), String>{
                 } => {
                     let diff_timestamp = new_timestamp.duration_since(before_timestamp).as_millis();
                     if mousestate.left() {
-                        mvp.update_translation_mouse(
+                        let delta = MVP::mouse_translation(
                             xrel as f32,
                             yrel as f32,
                             diff_timestamp as f32
                         );
+                        if !scene.translate_selected(&delta, &mvp.model_view()) {
+                            mvp.translate(&delta);
+                        }
                     } else if mousestate.middle() {
-                        mvp.update_rotate_matrix(
+                        let rotation = MVP::mouse_rotation(
                             xrel,
                             yrel,
                             diff_timestamp as f32
                         );
+                        if !scene.rotate_selected(&rotation, &mvp.model_view()) {
+                            mvp.rotate(&rotation);
+                        }
                     } else if mousestate.right() && xrel != 0 {
-                        mvp.update_z_rotate_matrix(xrel, diff_timestamp as f32);
+                        let rotation = MVP::z_rotation(xrel, diff_timestamp as f32);
+                        if !scene.rotate_selected(&rotation, &mvp.model_view()) {
+                            mvp.rotate(&rotation);
+                        }
                     }
                 }
                 sdl2::event::Event::MouseWheel {
@@ -149,33 +305,276 @@ fn run() -> Result<(), String>{
                     y,
                     direction
                 } => {
-                    if let sdl2::mouse::MouseWheelDirection::Flipped = direction {
-                        mvp.update_translation_wheel(y as f32)
+                    let delta = if let sdl2::mouse::MouseWheelDirection::Flipped = direction {
+                        MVP::wheel_translation(y as f32)
                     } else {
-                        mvp.update_translation_wheel(-y as f32)
+                        MVP::wheel_translation(-y as f32)
+                    };
+                    if !scene.translate_selected(&delta, &mvp.model_view()) {
+                        mvp.translate(&delta);
                     }
                 }
                 sdl2::event::Event::KeyDown {
                     timestamp: _,
                     window_id: _,
-                    keycode: Some(sdl2::keyboard::Keycode::F1),
+                    keycode: Some(keycode),
+                    scancode: _,
+                    keymod: sdl2::keyboard::Mod::NOMOD,
+                    repeat: false
+                } if TextureManager::function_key_index(keycode).is_some() => {
+                    let index = TextureManager::function_key_index(keycode).unwrap_or(0);
+                    for object in scene.targets_mut() {
+                        // The key of the shown texture switches back to the colors.
+                        if index == object.texture && object.model.is_texture_on() {
+                            object.model.set_texture_on(false);
+                        } else if index < textures.len() {
+                            object.set_texture(index, &textures);
+                            object.model.set_texture_on(true);
+                        }
+                    }
+                    update_title(window, &textures, &scene);
+                },
+                sdl2::event::Event::KeyDown {
+                    timestamp: _,
+                    window_id: _,
+                    keycode: Some(sdl2::keyboard::Keycode::PageDown),
+                    scancode: _,
+                    keymod: sdl2::keyboard::Mod::NOMOD,
+                    repeat: _
+                } => {
+                    for object in scene.targets_mut() {
+                        object.set_texture(textures.next_index(object.texture), &textures);
+                        object.model.set_texture_on(true);
+                    }
+                    update_title(window, &textures, &scene);
+                },
+                sdl2::event::Event::KeyDown {
+                    timestamp: _,
+                    window_id: _,
+                    keycode: Some(sdl2::keyboard::Keycode::PageUp),
+                    scancode: _,
+                    keymod: sdl2::keyboard::Mod::NOMOD,
+                    repeat: _
+                } => {
+                    for object in scene.targets_mut() {
+                        object.set_texture(textures.previous_index(object.texture), &textures);
+                        object.model.set_texture_on(true);
+                    }
+                    update_title(window, &textures, &scene);
+                },
+                sdl2::event::Event::KeyDown {
+                    timestamp: _,
+                    window_id: _,
+                    keycode: Some(sdl2::keyboard::Keycode::A),
+                    scancode: _,
+                    keymod: sdl2::keyboard::Mod::NOMOD,
+                    repeat: false
+                } => {
+                    for object in scene.targets_mut() {
+                        object.material.switch_alpha_mode();
+                        println!("{}: alpha mode: {:?}", object.name, object.material.alpha_mode());
+                    }
+                },
+                sdl2::event::Event::KeyDown {
+                    timestamp: _,
+                    window_id: _,
+                    keycode: Some(sdl2::keyboard::Keycode::U),
+                    scancode: _,
+                    keymod: sdl2::keyboard::Mod::NOMOD,
+                    repeat: false
+                } => {
+                    for object in scene.targets_mut() {
+                        object.model.set_uv_projection(object.model.uv_projection().next());
+                        println!("{}: uv projection: {:?}", object.name, object.model.uv_projection());
+                    }
+                },
+                sdl2::event::Event::KeyDown {
+                    timestamp: _,
+                    window_id: _,
+                    keycode: Some(sdl2::keyboard::Keycode::D),
+                    scancode: _,
+                    keymod: sdl2::keyboard::Mod::NOMOD,
+                    repeat: false
+                } => {
+                    for object in scene.targets_mut() {
+                        object.model.switch_lod();
+                    }
+                    scene.update_lods(&mvp);
+                    for object in scene.targets_mut() {
+                        println!(
+                            "{}: lod: {:?} (level {}/{}, {} triangles)",
+                            object.name, object.model.lod_selection(), object.model.lod_level(),
+                            object.model.lod_count() - 1, object.model.lod_triangle_count()
+                        );
+                    }
+                },
+                sdl2::event::Event::KeyDown {
+                    timestamp: _,
+                    window_id: _,
+                    keycode: Some(keycode @ (sdl2::keyboard::Keycode::Equals | sdl2::keyboard::Keycode::Plus
+                        | sdl2::keyboard::Keycode::KpPlus | sdl2::keyboard::Keycode::Minus
+                        | sdl2::keyboard::Keycode::KpMinus)),
+                    scancode: _,
+                    keymod: sdl2::keyboard::Mod::NOMOD,
+                    repeat: false
+                } => {
+                    let more = !matches!(keycode, sdl2::keyboard::Keycode::Minus | sdl2::keyboard::Keycode::KpMinus);
+                    for object in scene.targets_mut() {
+                        let level = object.model.subdivision_level();
+                        object.model.set_subdivision_level(if more { level + 1 } else { level.saturating_sub(1) });
+                        println!(
+                            "{}: subdivision: level {}, {} triangles",
+                            object.name, object.model.subdivision_level(), object.model.drawn_triangle_count()
+                        );
+                    }
+                },
+                sdl2::event::Event::KeyDown {
+                    timestamp: _,
+                    window_id: _,
+                    keycode: Some(sdl2::keyboard::Keycode::B),
+                    scancode: _,
+                    keymod: sdl2::keyboard::Mod::NOMOD,
+                    repeat: false
+                } => {
+                    bbox.switch();
+                    println!("bounding box: {}", if bbox.is_on() { "on" } else { "off" });
+                },
+                sdl2::event::Event::KeyDown {
+                    timestamp: _,
+                    window_id: _,
+                    keycode: Some(sdl2::keyboard::Keycode::I),
+                    scancode: _,
+                    keymod: sdl2::keyboard::Mod::NOMOD,
+                    repeat: false
+                } => {
+                    stats_overlay.switch();
+                    if stats_overlay.is_on() {
+                        for line in scene.stats_lines() {
+                            println!("{}", line);
+                        }
+                    }
+                },
+                sdl2::event::Event::KeyDown {
+                    timestamp: _,
+                    window_id: _,
+                    keycode: Some(sdl2::keyboard::Keycode::Tab),
+                    scancode: _,
+                    keymod: sdl2::keyboard::Mod::NOMOD,
+                    repeat: false
+                } => {
+                    scene.select_next();
+                    println!("selected: {}", scene.selection_name());
+                    stats_overlay.set_lines(&scene.stats_lines());
+                    update_title(window, &textures, &scene);
+                },
+                sdl2::event::Event::KeyDown {
+                    timestamp: _,
+                    window_id: _,
+                    keycode: Some(sdl2::keyboard::Keycode::M),
+                    scancode: _,
+                    keymod: sdl2::keyboard::Mod::NOMOD,
+                    repeat: false
+                } => {
+                    rendering.switch();
+                    println!("render mode: {:?}", rendering.mode());
+                },
+                sdl2::event::Event::KeyDown {
+                    timestamp: _,
+                    window_id: _,
+                    keycode: Some(sdl2::keyboard::Keycode::C),
+                    scancode: _,
+                    keymod: sdl2::keyboard::Mod::NOMOD,
+                    repeat: false
+                } => {
+                    culling.switch();
+                    println!("back-face culling: {}", if culling.is_on() { "on" } else { "off" });
+                },
+                sdl2::event::Event::KeyDown {
+                    timestamp: _,
+                    window_id: _,
+                    keycode: Some(sdl2::keyboard::Keycode::W),
+                    scancode: _,
+                    keymod: sdl2::keyboard::Mod::NOMOD,
+                    repeat: false
+                } => {
+                    culling.switch_front_face();
+                    println!("front face: {:?}", culling.front_face());
+                },
+                sdl2::event::Event::KeyDown {
+                    timestamp: _,
+                    window_id: _,
+                    keycode: Some(sdl2::keyboard::Keycode::O),
+                    scancode: _,
+                    keymod: sdl2::keyboard::Mod::NOMOD,
+                    repeat: false
+                } => {
+                    culling.switch_face_debug();
+                    println!("face orientation colors: {}", if culling.is_face_debug() { "on" } else { "off" });
+                },
+                sdl2::event::Event::KeyDown {
+                    timestamp: _,
+                    window_id: _,
+                    keycode: Some(sdl2::keyboard::Keycode::N),
+                    scancode: _,
+                    keymod: sdl2::keyboard::Mod::NOMOD,
+                    repeat: false
+                } => {
+                    if normal_map.is_loaded() {
+                        normal_map.switch();
+                        println!("normal map: {}", if normal_map.is_on() { "on" } else { "off" });
+                    } else {
+                        println!("normal map: none loaded (--normal-map)");
+                    }
+                },
+                sdl2::event::Event::KeyDown {
+                    timestamp: _,
+                    window_id: _,
+                    keycode: Some(sdl2::keyboard::Keycode::L),
                     scancode: _,
                     keymod: sdl2::keyboard::Mod::NOMOD,
                     repeat: false
                 } => {
-                    model.switch_texture();
+                    lighting.switch();
+                    println!("lighting: {}", if lighting.is_on() { "on" } else { "off" });
+                },
+                sdl2::event::Event::KeyDown {
+                    timestamp: _,
+                    window_id: _,
+                    keycode: Some(keycode @ (sdl2::keyboard::Keycode::Left | sdl2::keyboard::Keycode::Right
+                        | sdl2::keyboard::Keycode::Up | sdl2::keyboard::Keycode::Down)),
+                    scancode: _,
+                    keymod: sdl2::keyboard::Mod::NOMOD,
+                    repeat: _
+                } => {
+                    match keycode {
+                        sdl2::keyboard::Keycode::Left => lighting.move_key_light(-1, 0),
+                        sdl2::keyboard::Keycode::Right => lighting.move_key_light(1, 0),
+                        sdl2::keyboard::Keycode::Up => lighting.move_key_light(0, 1),
+                        _ => lighting.move_key_light(0, -1),
+                    }
                 },
                 _ => {}
             }
         }
+        scene.update_lods(&mvp);
+        scene.update_texture_fade(new_timestamp.duration_since(before_timestamp).as_secs_f32() * 1000.0);
         before_timestamp = new_timestamp;
 
         unsafe {
             gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
         }
+        if let Some(skybox) = &mut skybox {
+            skybox.draw(&mvp);
+        }
         program.set_used();
         mvp.set();
-        model.draw()?;
+        lighting.set();
+        normal_map.set();
+        culling.set();
+        scene.draw(&mut textures, &rendering)?;
+        culling.unset();
+        bbox.draw(&mvp, &scene.bounding_boxes());
+        stats_overlay.draw();
         window.gl_swap_window();
     }
 
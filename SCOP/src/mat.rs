@@ -11,6 +11,16 @@ pub struct Vec3d {
     pub d2: f32,
 }
 
+// A direction and a sign, like the tangents.
+#[derive(Copy, Clone, Debug)]
+#[repr(C, packed)]
+pub struct Vec4d {
+    pub d0: f32,
+    pub d1: f32,
+    pub d2: f32,
+    pub d3: f32,
+}
+
 #[derive(Copy, Clone, Debug)]
 #[repr(C, packed)]
 pub struct Vec2d {
@@ -127,6 +137,46 @@ impl Matrix {
         matrix
     }
 
+    pub fn transform(&self, vec: [f32; 4]) -> [f32; 4] {
+        let mut ret = [0.0; 4];
+        for (i, value) in ret.iter_mut().enumerate() {
+            for (k, v) in vec.iter().enumerate() {
+                *value += self.elem[i][k] * v;
+            }
+        }
+        ret
+    }
+
+    // Gauss-Jordan elimination with partial pivoting.
+    pub fn inverse(&self) -> Option<Self> {
+        let mut a = self.elem;
+        let mut inverse = Self::make_identity_matrix().elem;
+        for col in 0..4 {
+            let pivot = (col..4).max_by(|i, j| a[*i][col].abs().total_cmp(&a[*j][col].abs()))?;
+            if a[pivot][col].abs() < f32::EPSILON {
+                return None
+            }
+            a.swap(col, pivot);
+            inverse.swap(col, pivot);
+            let scale = 1.0 / a[col][col];
+            for j in 0..4 {
+                a[col][j] *= scale;
+                inverse[col][j] *= scale;
+            }
+            for row in 0..4 {
+                if row == col {
+                    continue;
+                }
+                let factor = a[row][col];
+                for j in 0..4 {
+                    a[row][j] -= factor * a[col][j];
+                    inverse[row][j] -= factor * inverse[col][j];
+                }
+            }
+        }
+        Some(Self::new(inverse))
+    }
+
     fn mul_i_j(&self, rhs: &Self, i: usize, j: usize) -> f32 {
         let mut ret = 0.0;
         for k in 0..4 {
@@ -190,6 +240,14 @@ impl Vec3d {
         self.d0 * rhs.d0 + self.d1 * rhs.d1 + self.d2 * rhs.d2
     }
 
+    pub fn cross_product(&self, rhs: &Self) -> Self {
+        (
+            self.d1 * rhs.d2 - self.d2 * rhs.d1,
+            self.d2 * rhs.d0 - self.d0 * rhs.d2,
+            self.d0 * rhs.d1 - self.d1 * rhs.d0,
+        ).into()
+    }
+
     pub fn add(&self, rhs: &Self) -> Self {
         (self.d0 + rhs.d0, self.d1 + rhs.d1, self.d2 + rhs.d2).into()
     }
@@ -203,6 +261,21 @@ impl Vec3d {
     }
 }
 
+impl Vec3d {
+    // A vertex array and the buffer holding the vertices must be bound.
+    pub unsafe fn vertex_attrib_pointer(stride: usize, location: usize, offset: usize) {
+        gl::EnableVertexAttribArray(location as gl::types::GLuint);
+        gl::VertexAttribPointer(
+            location as gl::types::GLuint,
+            3,
+            gl::FLOAT,
+            gl::FALSE,
+            stride as gl::types::GLint,
+            offset as *const gl::types::GLvoid,
+        );
+    }
+}
+
 impl From<(f32, f32, f32)> for Vec3d {
     fn from(other: (f32, f32, f32)) -> Self {
         Vec3d::new(other.0, other.1, other.2)
@@ -215,8 +288,44 @@ impl Vec2d {
     }
 }
 
+impl Vec2d {
+    // A vertex array and the buffer holding the vertices must be bound.
+    pub unsafe fn vertex_attrib_pointer(stride: usize, location: usize, offset: usize) {
+        gl::EnableVertexAttribArray(location as gl::types::GLuint);
+        gl::VertexAttribPointer(
+            location as gl::types::GLuint,
+            2,
+            gl::FLOAT,
+            gl::FALSE,
+            stride as gl::types::GLint,
+            offset as *const gl::types::GLvoid,
+        );
+    }
+}
+
 impl From<(f32, f32)> for Vec2d {
     fn from(other: (f32, f32)) -> Self {
         Vec2d::new(other.0, other.1)
     }
 }
+
+impl Vec4d {
+    pub fn new(d0: f32, d1: f32, d2: f32, d3: f32) -> Self {
+        Self { d0, d1, d2, d3 }
+    }
+}
+
+impl Vec4d {
+    // A vertex array and the buffer holding the vertices must be bound.
+    pub unsafe fn vertex_attrib_pointer(stride: usize, location: usize, offset: usize) {
+        gl::EnableVertexAttribArray(location as gl::types::GLuint);
+        gl::VertexAttribPointer(
+            location as gl::types::GLuint,
+            4,
+            gl::FLOAT,
+            gl::FALSE,
+            stride as gl::types::GLint,
+            offset as *const gl::types::GLvoid,
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use std::ffi::CString;
+
+
+const DEFAULT_ALPHA_CUTOFF: f32 = 0.5;
+const DEFAULT_SHININESS: f32 = 32.0;
+const DEFAULT_SPECULAR: f32 = 0.4;
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlphaMode {
+    Opaque,
+    Mask,
+    Blend,
+}
+
+#[derive(Debug)]
+pub struct Material {
+    alpha_mode: AlphaMode,
+    alpha_cutoff: f32,
+    shininess: f32,
+    specular: f32,
+    alpha_mode_id: i32,
+    alpha_cutoff_id: i32,
+    shininess_id: i32,
+    specular_id: i32,
+}
+
+impl AlphaMode {
+    fn next(&self) -> Self {
+        match self {
+            AlphaMode::Opaque => AlphaMode::Mask,
+            AlphaMode::Mask => AlphaMode::Blend,
+            AlphaMode::Blend => AlphaMode::Opaque,
+        }
+    }
+
+    fn to_uniform(self) -> i32 {
+        match self {
+            AlphaMode::Opaque => 0,
+            AlphaMode::Mask => 1,
+            AlphaMode::Blend => 2,
+        }
+    }
+}
+
+impl Material {
+    pub fn new(alpha_mode: AlphaMode) -> Self {
+        Self {
+            alpha_mode,
+            alpha_cutoff: DEFAULT_ALPHA_CUTOFF,
+            shininess: DEFAULT_SHININESS,
+            specular: DEFAULT_SPECULAR,
+            alpha_mode_id: 0,
+            alpha_cutoff_id: 0,
+            shininess_id: 0,
+            specular_id: 0,
+        }
+    }
+
+    pub fn set_program(&mut self, program_id: gl::types::GLuint) -> Result<(), String> {
+        self.alpha_mode_id = Self::get_uniform_location(program_id, "AlphaMode")?;
+        self.alpha_cutoff_id = Self::get_uniform_location(program_id, "AlphaCutoff")?;
+        self.shininess_id = Self::get_uniform_location(program_id, "Shininess")?;
+        self.specular_id = Self::get_uniform_location(program_id, "SpecularStrength")?;
+        Ok(())
+    }
+
+    fn get_uniform_location(program_id: gl::types::GLuint, name: &str) -> Result<i32, String> {
+        let name = CString::new(name).map_err(|_| "error: GetUniformLocation".to_string())?;
+        Ok(unsafe { gl::GetUniformLocation(program_id, name.as_ptr()) })
+    }
+
+    pub fn alpha_mode(&self) -> AlphaMode {
+        self.alpha_mode
+    }
+
+    pub fn switch_alpha_mode(&mut self) {
+        self.alpha_mode = self.alpha_mode.next();
+    }
+
+    // A texture with alpha is cut out unless it was already blended, one
+    // without is opaque.
+    pub fn set_texture_alpha(&mut self, has_alpha: bool) {
+        self.alpha_mode = match (has_alpha, self.alpha_mode) {
+            (false, _) => AlphaMode::Opaque,
+            (true, AlphaMode::Opaque) => AlphaMode::Mask,
+            (true, mode) => mode,
+        };
+    }
+
+    pub fn set(&self) {
+        unsafe {
+            gl::Uniform1i(self.alpha_mode_id, self.alpha_mode.to_uniform());
+            gl::Uniform1f(self.alpha_cutoff_id, self.alpha_cutoff);
+            gl::Uniform1f(self.shininess_id, self.shininess);
+            gl::Uniform1f(self.specular_id, self.specular);
+            if let AlphaMode::Blend = self.alpha_mode {
+                // Blended faces are not sorted, so they must not hide each other.
+                gl::Enable(gl::BLEND);
+                gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+                gl::DepthMask(gl::FALSE);
+            } else {
+                gl::Disable(gl::BLEND);
+                gl::DepthMask(gl::TRUE);
+            }
+        }
+    }
+
+    pub fn unset(&self) {
+        unsafe {
+            gl::Disable(gl::BLEND);
+            gl::DepthMask(gl::TRUE);
+        }
+    }
+}
@@ -1,28 +1,75 @@
 pub mod color_sample;
 pub mod sample;
+pub mod normal;
+pub mod uv;
+pub mod vertex;
+pub mod optimize;
+pub mod simplify;
+pub mod lod;
+pub mod bounds;
+pub mod stats;
+pub mod wire;
+pub mod subdivide;
+pub mod repair;
+pub mod export;
+pub mod tangent;
+#[cfg(test)]
+mod fixtures;
 
 use std::ffi::CString;
 use crate::mat::{Vec3d, Vec2d};
+use crate::gl_object::{VertexArray, Buffer};
+use uv::UvProjection;
+use vertex::{Vertex, WireVertex};
+use optimize::BufferStats;
+use lod::LodSelection;
+use stats::MeshStats;
+use subdivide::SubdivisionScheme;
+use normal::{NormalMode, DEFAULT_CREASE_ANGLE};
+
+
+pub const DEFAULT_FADE_MS: f32 = 400.0;
 
 
 pub struct Model {
     vertices: Vec<Vec3d>,
     colors: Vec<Vec3d>,
     uv: Vec<Vec2d>,
+    source_uv: Vec<Vec2d>,
+    uv_projection: UvProjection,
+    normals: Vec<Vec3d>,
+    normal_mode: NormalMode,
+    crease_angle: f32,
     indices: Vec<gl::types::GLuint>,
     index_set: usize,
     index_count: Vec<usize>,
-    vbo: gl::types::GLuint,
-    cbo: gl::types::GLuint,
-    tbo: gl::types::GLuint,
-    ebo: gl::types::GLuint,
-    max_size: f32,
+    vao: Option<VertexArray>,
+    vbo: Option<Buffer>,
+    ebo: Option<Buffer>,
+    draw_count: usize,
+    wire_vao: Option<VertexArray>,
+    wire_vbo: Option<Buffer>,
+    wire_count: usize,
+    weld_epsilon: Option<f32>,
+    buffer_stats: Option<BufferStats>,
     texture_on: TextureSwitch,
+    lods: Vec<Model>,
+    lod_selection: LodSelection,
+    lod_level: usize,
+    subdivisions: Vec<Model>,
+    subdivision_scheme: SubdivisionScheme,
+    subdivision_crease: Option<f32>,
+    subdivision_level: usize,
+    stats: Option<MeshStats>,
 }
 
+// `progress` runs linearly between 0 (colors) and 1 (texture) and the
+// shader gets it eased, so turning back mid-fade stays continuous.
 struct TextureSwitch {
     id: i32,
     texture_on: bool,
+    progress: f32,
+    fade_ms: f32,
 }
 
 impl Model {
@@ -31,15 +78,32 @@ impl Model {
             vertices: Vec::new(),
             colors: Vec::new(),
             uv: Vec::new(),
+            source_uv: Vec::new(),
+            uv_projection: UvProjection::Faces,
+            normals: Vec::new(),
+            normal_mode: NormalMode::Angle,
+            crease_angle: DEFAULT_CREASE_ANGLE,
             indices: Vec::new(),
             index_set: 0,
             index_count: Vec::new(),
-            vbo: 0,
-            cbo: 0,
-            tbo: 0,
-            ebo: 0,
-            max_size: 0.0,
-            texture_on: TextureSwitch::new()
+            vao: None,
+            vbo: None,
+            ebo: None,
+            draw_count: 0,
+            wire_vao: None,
+            wire_vbo: None,
+            wire_count: 0,
+            weld_epsilon: None,
+            buffer_stats: None,
+            texture_on: TextureSwitch::new(),
+            lods: Vec::new(),
+            lod_selection: LodSelection::Auto,
+            lod_level: 0,
+            subdivisions: Vec::new(),
+            subdivision_scheme: SubdivisionScheme::Auto,
+            subdivision_crease: None,
+            subdivision_level: 0,
+            stats: None,
         }
     }
 
@@ -47,14 +111,16 @@ impl Model {
         self.texture_on.set_program(program_id)
     }
 
-    pub fn get_max_size(&self) -> f32 {
-        self.max_size
-    }
-
+    #[allow(dead_code)]
     pub fn get_vertices(&self) -> &Vec<Vec3d> {
         &self.vertices
     }
 
+    #[allow(dead_code)]
+    pub fn get_normals(&self) -> &Vec<Vec3d> {
+        &self.normals
+    }
+
     pub fn get_index_set(&self) -> usize {
         self.index_set
     }
@@ -72,15 +138,6 @@ impl Model {
     }
 
     pub fn push_vertices(&mut self, vec: Vec3d) {
-        if vec.d0.abs() > self.max_size {
-            self.max_size = vec.d0.abs();
-        }
-        if vec.d1.abs() > self.max_size {
-            self.max_size = vec.d1.abs();
-        }
-        if vec.d2.abs() > self.max_size {
-            self.max_size = vec.d2.abs();
-        }
         self.vertices.push(vec);
     }
 
@@ -100,225 +157,209 @@ impl Model {
         self.vertices = vertices;
     }
 
-    fn set_uv_to_non_indices(&mut self) {
+    fn uv_to_non_indices(&self) -> Vec<Vec2d> {
+        let mut uv = Vec::with_capacity(self.vertices.len());
         for i in 0..self.vertices.len() {
             if i % 4 == 0 {
-                self.uv.push(Vec2d::new(0.0, 0.0));
+                uv.push(Vec2d::new(0.0, 0.0));
             } else if i % 4 == 1 {
-                self.uv.push(Vec2d::new(0.0, 1.0));
+                uv.push(Vec2d::new(0.0, 1.0));
             } else if i % 4 == 2 {
-                self.uv.push(Vec2d::new(1.0, 0.0));
+                uv.push(Vec2d::new(1.0, 0.0));
             } else {
-                self.uv.push(Vec2d::new(1.0, 1.0));
+                uv.push(Vec2d::new(1.0, 1.0));
             }
         }
+        uv
     }
 
-    fn set_uv_to_has_indices(&mut self) {
+    fn uv_to_has_indices(&self) -> Vec<Vec2d> {
+        let mut uv = Vec::with_capacity(self.vertices.len());
         let mut triangle_flag = false;
         for count in &self.index_count {
             if *count == 3 {
                 if triangle_flag {
-                    self.uv.push(Vec2d::new(0.0, 0.0));
-                    self.uv.push(Vec2d::new(0.0, 1.0));
-                    self.uv.push(Vec2d::new(1.0, 1.0));
+                    uv.push(Vec2d::new(0.0, 0.0));
+                    uv.push(Vec2d::new(0.0, 1.0));
+                    uv.push(Vec2d::new(1.0, 1.0));
                 } else {
-                    self.uv.push(Vec2d::new(0.0, 0.0));
-                    self.uv.push(Vec2d::new(1.0, 0.0));
-                    self.uv.push(Vec2d::new(1.0, 1.0));
+                    uv.push(Vec2d::new(0.0, 0.0));
+                    uv.push(Vec2d::new(1.0, 0.0));
+                    uv.push(Vec2d::new(1.0, 1.0));
 
                 }
                 triangle_flag = !triangle_flag;
             } else {
                 for i in 0..*count {
                     if i % 4 == 0 {
-                        self.uv.push(Vec2d::new(0.0, 0.0));
+                        uv.push(Vec2d::new(0.0, 0.0));
                     } else if i % 4 == 1 {
-                        self.uv.push(Vec2d::new(0.0, 1.0));
+                        uv.push(Vec2d::new(0.0, 1.0));
                     } else if i % 4 == 2 {
-                        self.uv.push(Vec2d::new(1.0, 1.0));
+                        uv.push(Vec2d::new(1.0, 1.0));
                     } else {
-                        self.uv.push(Vec2d::new(1.0, 0.0));
+                        uv.push(Vec2d::new(1.0, 0.0));
                     }
                 }
             }
         }
+        uv
+    }
+
+    // The same corners on every face.
+    fn default_uv(&self) -> Vec<Vec2d> {
+        if self.indices.is_empty() {
+            self.uv_to_non_indices()
+        } else {
+            self.uv_to_has_indices()
+        }
     }
 
     pub fn set_uv(&mut self) {
-        if !self.uv.is_empty() {
-            return;
-        } else if self.indices.is_empty() {
-            self.set_uv_to_non_indices();
+        // UVs set before the first call came with the model.
+        if self.source_uv.is_empty() && !self.uv.is_empty() {
+            self.source_uv = self.uv.clone();
+        }
+        if self.uv_projection != UvProjection::Faces {
+            self.uv = self.project_uv(self.uv_projection);
+        } else if !self.source_uv.is_empty() {
+            self.uv = self.source_uv.clone();
         } else {
-            self.set_uv_to_has_indices();
+            self.uv = self.default_uv();
         }
     }
 
-    pub fn set_buffers(&mut self) {
-        unsafe {
-            gl::GenBuffers(1, &mut self.vbo);
-            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
-            gl::BufferData(
-                gl::ARRAY_BUFFER,
-                (self.vertices.len() * std::mem::size_of::<Vec3d>()) as gl::types::GLsizeiptr,
-                self.vertices.as_ptr() as *const gl::types::GLvoid,
-                gl::STATIC_DRAW,
-            );
+    pub fn uv_projection(&self) -> UvProjection {
+        self.uv_projection
+    }
+
+    // Also updates the uploaded UVs once the buffers are set.
+    pub fn set_uv_projection(&mut self, projection: UvProjection) {
+        self.uv_projection = projection;
+        self.set_uv();
+        self.upload_buffers();
+        for lod in self.lods.iter_mut().chain(self.subdivisions.iter_mut()) {
+            lod.set_uv_projection(projection);
         }
-        unsafe {
-            gl::GenBuffers(1, &mut self.cbo);
-            gl::BindBuffer(gl::ARRAY_BUFFER, self.cbo);
-            gl::BufferData(
-                gl::ARRAY_BUFFER,
-                (self.colors.len() * std::mem::size_of::<Vec3d>()) as gl::types::GLsizeiptr,
-                self.colors.as_ptr() as *const gl::types::GLvoid,
-                gl::STATIC_DRAW,
-            );
+    }
+
+    // Colors, UVs or normals missing for a vertex are zero.
+    pub fn interleaved_vertices(&self) -> Vec<Vertex> {
+        let zero3 = Vec3d::new(0.0, 0.0, 0.0);
+        let tangents = self.tangents();
+        self.vertices.iter().enumerate()
+            .map(|(i, position)| Vertex {
+                position: *position,
+                color: self.colors.get(i).copied().unwrap_or(zero3),
+                uv: self.uv.get(i).copied().unwrap_or(Vec2d::new(0.0, 0.0)),
+                normal: self.normals.get(i).copied().unwrap_or(zero3),
+                tangent: tangents[i],
+            })
+            .collect()
+    }
+
+    pub fn set_buffers(&mut self) {
+        self.vao = Some(VertexArray::new());
+        self.vbo = Some(Buffer::array());
+        self.ebo = Some(Buffer::element_array());
+        self.wire_vao = Some(VertexArray::new());
+        self.wire_vbo = Some(Buffer::array());
+        self.upload_buffers();
+        for lod in self.lods.iter_mut().chain(self.subdivisions.iter_mut()) {
+            lod.set_buffers();
         }
-        unsafe {
-            gl::GenBuffers(1, &mut self.tbo);
-            gl::BindBuffer(gl::ARRAY_BUFFER, self.tbo);
-            gl::BufferData(
-                gl::ARRAY_BUFFER,
-                (self.uv.len() * std::mem::size_of::<Vec2d>()) as gl::types::GLsizeiptr,
-                self.uv.as_ptr() as *const gl::types::GLvoid,
-                gl::STATIC_DRAW,
-            );
+    }
+
+    // The element buffer binding is recorded in the VAO, so the VAO is bound first.
+    fn upload_buffers(&mut self) {
+        let (Some(vao), Some(vbo), Some(ebo)) = (&self.vao, &self.vbo, &self.ebo) else {
+            return;
+        };
+        let (vertices, indices, stats) = self.optimized_buffers();
+        vao.bind();
+        vbo.upload(&vertices, gl::STATIC_DRAW);
+        ebo.upload(&indices, gl::STATIC_DRAW);
+        self.draw_count = indices.len();
+        self.buffer_stats = Some(stats);
+        if let Some(wire_vbo) = &self.wire_vbo {
+            let vertices = self.wire_vertices();
+            wire_vbo.upload(&vertices, gl::STATIC_DRAW);
+            self.wire_count = vertices.len();
         }
-        if !self.indices.is_empty() {
-            unsafe {
-                gl::GenBuffers(1, &mut self.ebo);
-                gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.ebo);
-                gl::BufferData(
-                    gl::ELEMENT_ARRAY_BUFFER,
-                    (self.indices.len() * std::mem::size_of::<gl::types::GLuint>()) as gl::types::GLsizeiptr,
-                    self.indices.as_ptr() as *const gl::types::GLvoid,
-                    gl::STATIC_DRAW,
-                );
-            }
+    }
+
+    pub fn buffer_stats(&self) -> Option<BufferStats> {
+        self.buffer_stats
+    }
+
+    pub fn set_weld_epsilon(&mut self, epsilon: Option<f32>) {
+        self.weld_epsilon = epsilon;
+        for lod in self.lods.iter_mut().chain(self.subdivisions.iter_mut()) {
+            lod.set_weld_epsilon(epsilon);
         }
     }
 
     pub fn config_buffer(&self) {
-        unsafe {
-            gl::EnableVertexAttribArray(0);
-            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
-            gl::VertexAttribPointer(
-                0,
-                3,
-                gl::FLOAT,
-                gl::FALSE,
-                0,
-                std::ptr::null(),
-            );
+        for lod in self.lods.iter().chain(self.subdivisions.iter()) {
+            lod.config_buffer();
         }
-        unsafe {
-            gl::EnableVertexAttribArray(1);
-            gl::BindBuffer(gl::ARRAY_BUFFER, self.cbo);
-            gl::VertexAttribPointer(
-                1,
-                3,
-                gl::FLOAT,
-                gl::FALSE,
-                0,
-                std::ptr::null(),
-            );
-        }
-        unsafe {
-            gl::EnableVertexAttribArray(2);
-            gl::BindBuffer(gl::ARRAY_BUFFER, self.tbo);
-            gl::VertexAttribPointer(
-                2,
-                2,
-                gl::FLOAT,
-                gl::FALSE,
-                0,
-                std::ptr::null(),
-            );
+        let (Some(vao), Some(vbo)) = (&self.vao, &self.vbo) else {
+            return;
+        };
+        vao.bind();
+        vbo.bind();
+        Vertex::vertex_attrib_pointers();
+        if let (Some(vao), Some(vbo)) = (&self.wire_vao, &self.wire_vbo) {
+            vao.bind();
+            vbo.bind();
+            WireVertex::vertex_attrib_pointers();
         }
     }
 
-    pub fn switch_texture(&mut self) {
-        self.texture_on.switch();
+    pub fn set_texture_on(&mut self, texture_on: bool) {
+        self.texture_on.set_on(texture_on);
+    }
+
+    pub fn is_texture_on(&self) -> bool {
+        self.texture_on.is_on()
+    }
+
+    pub fn set_texture_fade_ms(&mut self, fade_ms: f32) {
+        self.texture_on.set_fade_ms(fade_ms);
+    }
+
+    pub fn update_texture_fade(&mut self, elapsed_ms: f32) {
+        self.texture_on.update(elapsed_ms);
     }
 
     pub fn draw(&self) -> Result<(), String>{
+        let lod = self.drawn();
+        let Some(vao) = &lod.vao else {
+            return Err("error: model buffers are not set".to_string())
+        };
+        vao.bind();
         self.texture_on.set();
-        if self.indices.is_empty() {
-            unsafe {
-                gl::DrawArrays(
-                    gl::TRIANGLES,
-                    0,
-                    (self.vertices.len() * 3) as i32,
-                );
-            }
-        } else {
-            match self.index_set {
-                3 => {
-                    self.draw_one_elements(
-                        0,
-                        self.indices.len() as i32,
-                        3
-                    )?;
-                },
-                1 => {
-                    let mut index = 0;
-                    let mut count_index = 0;
-                    let mut index_set = 0;
-                    let mut start_index = 0;
-                    while index < self.indices.len() &&
-                            count_index < self.index_count.len() {
-                        if index == start_index {
-                            index_set = self.index_count[count_index];
-                            index += index_set;
-                            count_index += 1;
-                        } else if index_set != 3 || index_set != self.index_count[count_index] {
-                            self.draw_one_elements(
-                                start_index,
-                                (index - start_index) as i32,
-                                index_set as i32
-                            )?;
-                            start_index = index;
-                        } else {
-                            index += index_set;
-                            count_index += 1;
-                        }
-                    }
-                    if index != start_index {
-                        let count = if index != self.indices.len() {
-                            (index - start_index).saturating_sub(1)
-                        } else {
-                            index - start_index
-                        };
-                        if count > 0 {
-                            self.draw_one_elements(
-                                start_index,
-                                count as i32,
-                                index_set as i32
-                            )?;
-                        }
-                    }
-                },
-                _ => return Err(format!("error: index set is 3 or 4"))
-            }
+        unsafe {
+            gl::DrawElements(
+                gl::TRIANGLES,
+                lod.draw_count as i32,
+                gl::UNSIGNED_INT,
+                std::ptr::null(),
+            );
         }
         Ok(())
     }
 
-    fn draw_one_elements(&self, index: usize, count: i32, index_set: i32) -> Result<(), String>{
-        let mode = match index_set {
-            1 | 2 => return Err(format!("error: unsupported index set 1 or 2")),
-            3 => gl::TRIANGLES,
-            _ => gl::TRIANGLE_FAN,
+    // Unwelded triangles with barycentric coordinates, for the edge overlays.
+    pub fn draw_wire(&self) -> Result<(), String> {
+        let lod = self.drawn();
+        let Some(vao) = &lod.wire_vao else {
+            return Err("error: model buffers are not set".to_string())
         };
+        vao.bind();
+        self.texture_on.set();
         unsafe {
-            gl::DrawElements(
-                mode,
-                count,
-                gl::UNSIGNED_INT,
-                (index * std::mem::size_of::<gl::types::GLuint>()) as *const std::os::raw::c_void
-            );
+            gl::DrawArrays(gl::TRIANGLES, 0, lod.wire_count as i32);
         }
         Ok(())
     }
@@ -326,23 +367,51 @@ impl Model {
 
 impl TextureSwitch {
     pub fn new() -> Self {
-        Self { id: 0, texture_on: false }
+        Self { id: 0, texture_on: false, progress: 0.0, fade_ms: DEFAULT_FADE_MS }
     }
 
     pub fn set_program(&mut self, program_id: gl::types::GLuint) -> Result<(), String> {
-        let name = CString::new("IsTexture").map_err(|_| "error: GetUniformLocation".to_string())?;
+        let name = CString::new("TextureBlend").map_err(|_| "error: GetUniformLocation".to_string())?;
         let id = unsafe {gl::GetUniformLocation(program_id, name.as_ptr()) };
         self.id = id;
         Ok(())
     }
 
-    pub fn switch(&mut self) {
-        self.texture_on = !self.texture_on;
+    pub fn set_on(&mut self, texture_on: bool) {
+        self.texture_on = texture_on;
+    }
+
+    pub fn is_on(&self) -> bool {
+        self.texture_on
+    }
+
+    pub fn set_fade_ms(&mut self, fade_ms: f32) {
+        self.fade_ms = fade_ms;
+    }
+
+    pub fn update(&mut self, elapsed_ms: f32) {
+        let target = if self.texture_on { 1.0 } else { 0.0 };
+        if self.fade_ms <= 0.0 {
+            self.progress = target;
+            return;
+        }
+        let step = elapsed_ms / self.fade_ms;
+        self.progress = if self.progress < target {
+            (self.progress + step).min(target)
+        } else {
+            (self.progress - step).max(target)
+        };
+    }
+
+    fn blend(&self) -> f32 {
+        // Cubic ease in / out.
+        let t = self.progress;
+        t * t * (3.0 - 2.0 * t)
     }
 
     pub fn set(&self) {
         unsafe {
-            gl::Uniform1i(self.id, self.texture_on as i32);
+            gl::Uniform1f(self.id, self.blend());
         }
     }
 }
@@ -0,0 +1,242 @@
+use std::collections::HashSet;
+
+use super::Model;
+use crate::mat::Vec3d;
+
+
+// Relative slack of the containment test, for the rounding of the
+// circumsphere solutions.
+const SPHERE_TOLERANCE: f64 = 1e-7;
+
+
+#[derive(Debug, Clone, Copy)]
+struct Sphere {
+    center: [f64; 3],
+    radius: f64,
+}
+
+impl Model {
+    pub fn bounding_box(&self) -> (Vec3d, Vec3d) {
+        if self.vertices.is_empty() {
+            return (Vec3d::new(0.0, 0.0, 0.0), Vec3d::new(0.0, 0.0, 0.0))
+        }
+        let mut min = Vec3d::new(f32::MAX, f32::MAX, f32::MAX);
+        let mut max = Vec3d::new(f32::MIN, f32::MIN, f32::MIN);
+        for v in &self.vertices {
+            min = Vec3d::new(min.d0.min(v.d0), min.d1.min(v.d1), min.d2.min(v.d2));
+            max = Vec3d::new(max.d0.max(v.d0), max.d1.max(v.d1), max.d2.max(v.d2));
+        }
+        (min, max)
+    }
+
+    pub fn bounding_box_center(&self) -> Vec3d {
+        let (min, max) = self.bounding_box();
+        min.add(&max).mul(0.5)
+    }
+
+    // Smallest sphere around the vertices, by Welzl's algorithm: the points
+    // are shuffled, and a point out of the sphere so far is on the boundary
+    // of the sphere of the points before it.
+    pub fn bounding_sphere(&self) -> (Vec3d, f32) {
+        let mut seen = HashSet::new();
+        let mut points: Vec<[f64; 3]> = self.vertices.iter()
+            .filter(|v| seen.insert([v.d0, v.d1, v.d2].map(|x| (x + 0.0).to_bits())))
+            .map(|v| [v.d0 as f64, v.d1 as f64, v.d2 as f64])
+            .collect();
+        if points.is_empty() {
+            return (Vec3d::new(0.0, 0.0, 0.0), 0.0)
+        }
+        shuffle(&mut points);
+        let mut sphere = Sphere::from_points(&[points[0]]);
+        for i in 1..points.len() {
+            if sphere.contains(&points[i]) {
+                continue;
+            }
+            sphere = Sphere::from_points(&[points[i]]);
+            for j in 0..i {
+                if sphere.contains(&points[j]) {
+                    continue;
+                }
+                sphere = Sphere::from_points(&[points[i], points[j]]);
+                for k in 0..j {
+                    if sphere.contains(&points[k]) {
+                        continue;
+                    }
+                    sphere = Sphere::from_points(&[points[i], points[j], points[k]]);
+                    for l in 0..k {
+                        if !sphere.contains(&points[l]) {
+                            sphere = Sphere::from_points(&[points[i], points[j], points[k], points[l]]);
+                        }
+                    }
+                }
+            }
+        }
+        let c = sphere.center;
+        (Vec3d::new(c[0] as f32, c[1] as f32, c[2] as f32), sphere.radius as f32)
+    }
+}
+
+impl Sphere {
+    fn contains(&self, p: &[f64; 3]) -> bool {
+        distance(&self.center, p) <= self.radius * (1.0 + SPHERE_TOLERANCE) + SPHERE_TOLERANCE
+    }
+
+    // Smallest sphere with all the points on its boundary. Points in a
+    // degenerate position fall back to the best sphere of fewer of them.
+    fn from_points(points: &[[f64; 3]]) -> Self {
+        match points {
+            [a] => Self { center: *a, radius: 0.0 },
+            [a, b] => {
+                let center = [0, 1, 2].map(|i| (a[i] + b[i]) / 2.0);
+                Self { center, radius: distance(&center, a) }
+            },
+            [a, b, c] => Self::circumscribed(a, b, c).unwrap_or_else(|| Self::smallest_of(points, 2)),
+            [a, b, c, d] => Self::circumscribed4(a, b, c, d).unwrap_or_else(|| Self::smallest_of(points, 3)),
+            _ => Self { center: [0.0; 3], radius: 0.0 },
+        }
+    }
+
+    // The smallest sphere through `size` of the points holding all of them.
+    fn smallest_of(points: &[[f64; 3]], size: usize) -> Self {
+        let n = points.len();
+        let subsets: Vec<Vec<[f64; 3]>> = match size {
+            2 => (0..n).flat_map(|i| (i + 1..n).map(move |j| vec![points[i], points[j]])).collect(),
+            _ => (0..n).map(|skip| (0..n).filter(|i| *i != skip).map(|i| points[i]).collect()).collect(),
+        };
+        subsets.iter()
+            .map(|subset| Self::from_points(subset))
+            .filter(|sphere| points.iter().all(|p| sphere.contains(p)))
+            .min_by(|a, b| a.radius.total_cmp(&b.radius))
+            .unwrap_or(Self { center: points[0], radius: f64::MAX })
+    }
+
+    // Circumcircle of a triangle, None when the points are on a line.
+    fn circumscribed(a: &[f64; 3], b: &[f64; 3], c: &[f64; 3]) -> Option<Self> {
+        let ab = sub(b, a);
+        let ac = sub(c, a);
+        let normal = cross(&ab, &ac);
+        let denominator = 2.0 * dot(&normal, &normal);
+        if denominator <= f64::EPSILON * dot(&ab, &ab) * dot(&ac, &ac) {
+            return None
+        }
+        let t = add(
+            &scale(&cross(&normal, &ab), dot(&ac, &ac)),
+            &scale(&cross(&ac, &normal), dot(&ab, &ab)),
+        );
+        let offset = scale(&t, 1.0 / denominator);
+        Some(Self { center: add(a, &offset), radius: dot(&offset, &offset).sqrt() })
+    }
+
+    // Circumsphere of a tetrahedron, None when the points are on a plane.
+    fn circumscribed4(a: &[f64; 3], b: &[f64; 3], c: &[f64; 3], d: &[f64; 3]) -> Option<Self> {
+        let (ab, ac, ad) = (sub(b, a), sub(c, a), sub(d, a));
+        let determinant = dot(&ab, &cross(&ac, &ad));
+        let scale_length = dot(&ab, &ab).sqrt() * dot(&ac, &ac).sqrt() * dot(&ad, &ad).sqrt();
+        if determinant.abs() <= f64::EPSILON * scale_length {
+            return None
+        }
+        let t = add(
+            &add(&scale(&cross(&ac, &ad), dot(&ab, &ab)), &scale(&cross(&ad, &ab), dot(&ac, &ac))),
+            &scale(&cross(&ab, &ac), dot(&ad, &ad)),
+        );
+        let offset = scale(&t, 1.0 / (2.0 * determinant));
+        Some(Self { center: add(a, &offset), radius: dot(&offset, &offset).sqrt() })
+    }
+}
+
+// Fisher-Yates with a fixed xorshift seed, so the result does not change
+// between runs.
+fn shuffle(points: &mut [[f64; 3]]) {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    for i in (1..points.len()).rev() {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        points.swap(i, (state % (i as u64 + 1)) as usize);
+    }
+}
+
+fn sub(a: &[f64; 3], b: &[f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn add(a: &[f64; 3], b: &[f64; 3]) -> [f64; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn scale(a: &[f64; 3], s: f64) -> [f64; 3] {
+    [a[0] * s, a[1] * s, a[2] * s]
+}
+
+fn dot(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: &[f64; 3], b: &[f64; 3]) -> [f64; 3] {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+fn distance(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    let d = sub(a, b);
+    dot(&d, &d).sqrt()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::fixtures::{grid, mesh, tetrahedron};
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-5, "{} != {}", a, b);
+    }
+
+    fn contains_all(model: &Model, center: &Vec3d, radius: f32) -> bool {
+        model.vertices.iter().all(|v| v.minus(center).length() <= radius * (1.0 + 1e-5))
+    }
+
+    #[test]
+    fn regular_tetrahedron_gets_its_circumsphere() {
+        let model = mesh(
+            &[[1.0, 1.0, 1.0], [1.0, -1.0, -1.0], [-1.0, 1.0, -1.0], [-1.0, -1.0, 1.0]],
+            &[&[0, 1, 2], &[0, 3, 1], &[0, 2, 3], &[1, 3, 2]],
+        );
+        let (center, radius) = model.bounding_sphere();
+        assert_close(center.length(), 0.0);
+        assert_close(radius, 3.0f32.sqrt());
+    }
+
+    // The circumcenter of this one is outside of it, the smallest sphere
+    // goes through the slanted face only.
+    #[test]
+    fn corner_tetrahedron_gets_a_smaller_sphere() {
+        let model = tetrahedron();
+        let (center, radius) = model.bounding_sphere();
+        for c in [center.d0, center.d1, center.d2] {
+            assert_close(c, 1.0 / 3.0);
+        }
+        assert_close(radius, (2.0f32 / 3.0).sqrt());
+        assert!(contains_all(&model, &center, radius));
+    }
+
+    #[test]
+    fn sphere_contains_every_vertex() {
+        let mut model = grid(6);
+        for (i, v) in model.vertices.iter_mut().enumerate() {
+            v.d2 = ((i * 7919) % 13) as f32 * 0.37;
+        }
+        let (center, radius) = model.bounding_sphere();
+        assert!(contains_all(&model, &center, radius));
+        // Smaller than the sphere around the bounding box.
+        let (min, max) = model.bounding_box();
+        assert!(radius <= max.minus(&min).length() / 2.0);
+    }
+
+    #[test]
+    fn collinear_points_get_their_middle() {
+        let model = mesh(&[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [4.0, 0.0, 0.0]], &[&[0, 1, 2]]);
+        let (center, radius) = model.bounding_sphere();
+        assert_close(center.d0, 2.0);
+        assert_close(radius, 2.0);
+    }
+}
@@ -0,0 +1,24 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use super::Model;
+
+
+impl Model {
+    // The positions and faces, what `Loader` reads back.
+    pub fn write_obj(&self, path: &str) -> Result<(), String> {
+        let file = File::create(path).map_err(|e| format!("error: {}: {}", path, e))?;
+        let mut writer = BufWriter::new(file);
+        let mut write = || -> std::io::Result<()> {
+            for v in &self.vertices {
+                writeln!(writer, "v {} {} {}", { v.d0 }, { v.d1 }, { v.d2 })?;
+            }
+            for face in self.faces() {
+                let indices: Vec<String> = face.iter().map(|i| (i + 1).to_string()).collect();
+                writeln!(writer, "f {}", indices.join(" "))?;
+            }
+            writer.flush()
+        };
+        write().map_err(|e| format!("error: {}: {}", path, e))
+    }
+}
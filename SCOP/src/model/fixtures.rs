@@ -0,0 +1,52 @@
+use super::Model;
+use crate::mat::Vec3d;
+
+
+// Small meshes for the tests, with counter-clockwise faces seen from outside.
+pub fn mesh(positions: &[[f32; 3]], faces: &[&[u32]]) -> Model {
+    let mut model = Model::new();
+    for [x, y, z] in positions {
+        model.push_vertices(Vec3d::new(*x, *y, *z));
+    }
+    for face in faces {
+        for index in *face {
+            model.push_indices(*index);
+        }
+        model.push_index_count(face.len());
+    }
+    model
+}
+
+// The unit cube, as 6 quads.
+pub fn cube() -> Model {
+    mesh(
+        &[
+            [0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0],
+            [0.0, 0.0, 1.0], [1.0, 0.0, 1.0], [1.0, 1.0, 1.0], [0.0, 1.0, 1.0],
+        ],
+        &[&[0, 3, 2, 1], &[4, 5, 6, 7], &[0, 1, 5, 4], &[3, 7, 6, 2], &[0, 4, 7, 3], &[1, 2, 6, 5]],
+    )
+}
+
+pub fn tetrahedron() -> Model {
+    mesh(
+        &[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+        &[&[0, 2, 1], &[0, 1, 3], &[0, 3, 2], &[1, 2, 3]],
+    )
+}
+
+// `n` x `n` unit quads in the z = 0 plane, facing +Z. Vertex (x, y) is
+// y * (n + 1) + x.
+pub fn grid(n: usize) -> Model {
+    let positions: Vec<[f32; 3]> = (0..=n)
+        .flat_map(|y| (0..=n).map(move |x| [x as f32, y as f32, 0.0]))
+        .collect();
+    let quads: Vec<[u32; 4]> = (0..n)
+        .flat_map(|y| (0..n).map(move |x| {
+            let a = (y * (n + 1) + x) as u32;
+            [a, a + 1, a + n as u32 + 2, a + n as u32 + 1]
+        }))
+        .collect();
+    let faces: Vec<&[u32]> = quads.iter().map(|q| &q[..]).collect();
+    mesh(&positions, &faces)
+}
@@ -0,0 +1,89 @@
+use super::Model;
+
+
+// Each level keeps half the triangles of the one before.
+const LOD_RATIO: f32 = 0.5;
+const LOD_MIN_TRIANGLES: usize = 16;
+// Screen radius, in halves of the window height, under which the first
+// simplified level is drawn. Each next level starts at 1/sqrt(2) of it, as
+// the covered area halves with the triangles.
+const LOD_SWITCH_RADIUS: f32 = 0.6;
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LodSelection {
+    Auto,
+    Level(usize),
+}
+
+impl Model {
+    // Levels past the full model, built from each other.
+    pub fn build_lods(&mut self, levels: usize) {
+        let mut lods: Vec<Model> = Vec::new();
+        for _ in 0..levels {
+            let previous = lods.last().unwrap_or(self);
+            let triangles = previous.triangle_count();
+            let target = (triangles as f32 * LOD_RATIO) as usize;
+            if target < LOD_MIN_TRIANGLES {
+                break;
+            }
+            let lod = previous.simplify(target, None);
+            // Boundaries and seams can stop the simplification early.
+            if lod.triangle_count() >= triangles {
+                break;
+            }
+            lods.push(lod);
+        }
+        self.lods = lods;
+        self.lod_level = 0;
+    }
+
+    pub fn lod_count(&self) -> usize {
+        self.lods.len() + 1
+    }
+
+    pub fn lod_selection(&self) -> LodSelection {
+        self.lod_selection
+    }
+
+    pub fn lod_level(&self) -> usize {
+        self.lod_level
+    }
+
+    // Auto, then every level from the full model.
+    pub fn switch_lod(&mut self) {
+        self.lod_selection = match self.lod_selection {
+            LodSelection::Auto => LodSelection::Level(0),
+            LodSelection::Level(level) if level + 1 < self.lod_count() => LodSelection::Level(level + 1),
+            LodSelection::Level(_) => LodSelection::Auto,
+        };
+        if let LodSelection::Level(level) = self.lod_selection {
+            self.lod_level = level;
+        }
+    }
+
+    // `screen_radius` is the radius of the bounding sphere on screen, in
+    // halves of the window height.
+    pub fn update_lod(&mut self, screen_radius: f32) {
+        if self.lod_selection != LodSelection::Auto {
+            return;
+        }
+        let level = if screen_radius <= 0.0 {
+            self.lods.len()
+        } else {
+            (2.0 * (LOD_SWITCH_RADIUS / screen_radius).log2() + 1.0).floor().max(0.0) as usize
+        };
+        self.lod_level = level.min(self.lods.len());
+    }
+
+    pub fn lod_triangle_count(&self) -> usize {
+        self.lod().draw_count / 3
+    }
+
+    pub(super) fn lod(&self) -> &Model {
+        match self.lod_level {
+            0 => self,
+            level => &self.lods[level - 1],
+        }
+    }
+}
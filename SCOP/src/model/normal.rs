@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use super::Model;
+use crate::mat::Vec3d;
+
+
+pub const DEFAULT_CREASE_ANGLE: f32 = 60.0;
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NormalMode {
+    Flat,
+    Area,
+    Angle,
+}
+
+impl FromStr for NormalMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "flat" => Ok(NormalMode::Flat),
+            "area" => Ok(NormalMode::Area),
+            "angle" => Ok(NormalMode::Angle),
+            _ => Err(format!("error: unknown normal mode: {}", s)),
+        }
+    }
+}
+
+struct Face {
+    corners: Vec<usize>,
+    // Newell normal, its length is twice the polygon area.
+    normal: Vec3d,
+}
+
+impl Model {
+    // Polygons as lists of vertex indices. Models without indices are drawn
+    // as a triangle list.
+    pub fn faces(&self) -> Vec<Vec<usize>> {
+        if self.indices.is_empty() {
+            return (0..self.vertices.len() / 3).map(|i| vec![i * 3, i * 3 + 1, i * 3 + 2]).collect()
+        }
+        let mut faces = Vec::with_capacity(self.index_count.len());
+        let mut start = 0;
+        for count in &self.index_count {
+            if start + count > self.indices.len() {
+                break;
+            }
+            faces.push(self.indices[start..start + count].iter().map(|i| *i as usize).collect());
+            start += count;
+        }
+        faces
+    }
+
+    // Normals are per vertex, and every vertex is a corner of one face after
+    // `resolve_duplicate_indices`. Smooth normals average the faces around the
+    // same position whose normals are within `crease_angle` degrees.
+    pub fn set_normals(&mut self, mode: NormalMode, crease_angle: f32) {
+        self.normal_mode = mode;
+        self.crease_angle = crease_angle;
+        let faces: Vec<Face> = self.faces().into_iter()
+            .map(|corners| {
+                let normal = self.newell_normal(&corners);
+                Face { corners, normal }
+            })
+            .collect();
+        let mut normals = vec![Vec3d::new(0.0, 0.0, 1.0); self.vertices.len()];
+        if let NormalMode::Flat = mode {
+            for face in &faces {
+                for corner in &face.corners {
+                    normals[*corner] = unit_or_z(&face.normal);
+                }
+            }
+            self.normals = normals;
+            return;
+        }
+
+        // (face, position in the face) of the corners sharing a position.
+        let mut groups: HashMap<[u32; 3], Vec<(usize, usize)>> = HashMap::new();
+        for (f, face) in faces.iter().enumerate() {
+            for (k, corner) in face.corners.iter().enumerate() {
+                groups.entry(position_key(&self.vertices[*corner])).or_default().push((f, k));
+            }
+        }
+        let cos_crease = crease_angle.to_radians().cos();
+        for group in groups.values() {
+            for (f, k) in group {
+                let face_normal = unit_or_z(&faces[*f].normal);
+                let mut sum = Vec3d::new(0.0, 0.0, 0.0);
+                for (g, l) in group {
+                    let other = unit_or_z(&faces[*g].normal);
+                    if f != g && face_normal.inner_product(&other) < cos_crease {
+                        continue;
+                    }
+                    let weight = match mode {
+                        NormalMode::Area => faces[*g].normal.length(),
+                        _ => self.corner_angle(&faces[*g].corners, *l),
+                    };
+                    sum = sum.add(&other.mul(weight));
+                }
+                normals[faces[*f].corners[*k]] = if sum.length() > 0.0 { sum.normalize() } else { face_normal };
+            }
+        }
+        self.normals = normals;
+    }
+
+    pub fn newell_normal(&self, corners: &[usize]) -> Vec3d {
+        let mut normal = Vec3d::new(0.0, 0.0, 0.0);
+        for i in 0..corners.len() {
+            let a = self.vertices[corners[i]];
+            let b = self.vertices[corners[(i + 1) % corners.len()]];
+            normal = normal.add(&a.cross_product(&b));
+        }
+        normal
+    }
+
+    pub(super) fn corner_angle(&self, corners: &[usize], k: usize) -> f32 {
+        let n = corners.len();
+        let p = self.vertices[corners[k]];
+        let a = self.vertices[corners[(k + n - 1) % n]].minus(&p);
+        let b = self.vertices[corners[(k + 1) % n]].minus(&p);
+        let length = a.length() * b.length();
+        if length == 0.0 {
+            return 0.0
+        }
+        (a.inner_product(&b) / length).clamp(-1.0, 1.0).acos()
+    }
+}
+
+fn unit_or_z(v: &Vec3d) -> Vec3d {
+    if v.length() > 0.0 { v.normalize() } else { Vec3d::new(0.0, 0.0, 1.0) }
+}
+
+pub(super) fn position_key(v: &Vec3d) -> [u32; 3] {
+    // +0.0 and -0.0 are the same position.
+    [v.d0 + 0.0, v.d1 + 0.0, v.d2 + 0.0].map(|x| x.to_bits())
+}
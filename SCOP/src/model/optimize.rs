@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+
+use super::Model;
+use super::vertex::Vertex;
+
+
+const CACHE_SIZE: usize = 32;
+const CACHE_DECAY_POWER: f32 = 1.5;
+const LAST_TRIANGLE_SCORE: f32 = 0.75;
+const VALENCE_BOOST_SCALE: f32 = 2.0;
+const VALENCE_BOOST_POWER: f32 = 0.5;
+// FIFO cache used to measure the ACMR, about the size of real hardware.
+const ACMR_CACHE_SIZE: usize = 16;
+
+
+#[derive(Debug, Clone, Copy)]
+pub struct BufferStats {
+    pub corners: usize,
+    pub vertices: usize,
+    pub triangles: usize,
+    pub acmr_before: f32,
+    pub acmr_after: f32,
+}
+
+impl Model {
+    // Vertices and triangle indices as uploaded: polygons are split into
+    // fans, corners equal in every attribute are welded, then triangles and
+    // vertices are reordered for the vertex cache and fetch locality.
+    pub fn optimized_buffers(&self) -> (Vec<Vertex>, Vec<u32>, BufferStats) {
+        let corners = self.interleaved_vertices();
+        let mut triangles = Vec::new();
+        for face in self.faces() {
+            for i in 1..face.len().saturating_sub(1) {
+                triangles.extend([face[0], face[i], face[i + 1]].map(|c| c as u32));
+            }
+        }
+        let (vertices, indices) = weld(&corners, &triangles, self.weld_epsilon);
+        let acmr_before = acmr(&indices, ACMR_CACHE_SIZE);
+        let indices = optimize_vertex_cache(&indices, vertices.len());
+        let (vertices, indices) = optimize_vertex_fetch(&vertices, &indices);
+        let stats = BufferStats {
+            corners: self.vertices.len(),
+            vertices: vertices.len(),
+            triangles: indices.len() / 3,
+            acmr_before,
+            acmr_after: acmr(&indices, ACMR_CACHE_SIZE),
+        };
+        (vertices, indices, stats)
+    }
+}
+
+fn attribute_key(v: &Vertex) -> [u32; 15] {
+    let (p, c, t, n, g) = (v.position, v.color, v.uv, v.normal, v.tangent);
+    // +0.0 and -0.0 are the same value.
+    [p.d0, p.d1, p.d2, c.d0, c.d1, c.d2, t.d0, t.d1, n.d0, n.d1, n.d2, g.d0, g.d1, g.d2, g.d3].map(|x| (x + 0.0).to_bits())
+}
+
+fn same_except_position(a: &Vertex, b: &Vertex) -> bool {
+    attribute_key(a)[3..] == attribute_key(b)[3..]
+}
+
+fn cell(v: &Vertex, epsilon: f32) -> [i64; 3] {
+    let p = v.position;
+    [p.d0, p.d1, p.d2].map(|x| (x / epsilon).floor() as i64)
+}
+
+// Merges the corners used by `indices`. With an epsilon, positions closer
+// than it are merged too when the other attributes are equal, looking up
+// the neighbouring cells of a spatial hash.
+pub fn weld(corners: &[Vertex], indices: &[u32], epsilon: Option<f32>) -> (Vec<Vertex>, Vec<u32>) {
+    let mut vertices: Vec<Vertex> = Vec::new();
+    let mut remap: Vec<Option<u32>> = vec![None; corners.len()];
+    let mut exact: HashMap<[u32; 15], u32> = HashMap::new();
+    let mut grid: HashMap<[i64; 3], Vec<u32>> = HashMap::new();
+    let epsilon = epsilon.filter(|e| *e > 0.0);
+    let mut result = Vec::with_capacity(indices.len());
+    for index in indices {
+        if let Some(welded) = remap[*index as usize] {
+            result.push(welded);
+            continue;
+        }
+        let corner = &corners[*index as usize];
+        let found = match epsilon {
+            None => exact.get(&attribute_key(corner)).copied(),
+            Some(epsilon) => {
+                let [x, y, z] = cell(corner, epsilon);
+                let mut found = None;
+                'search: for dx in -1..=1 {
+                    for dy in -1..=1 {
+                        for dz in -1..=1 {
+                            for candidate in grid.get(&[x + dx, y + dy, z + dz]).into_iter().flatten() {
+                                let other = &vertices[*candidate as usize];
+                                let (a, b) = (corner.position, other.position);
+                                if a.minus(&b).length() <= epsilon && same_except_position(corner, other) {
+                                    found = Some(*candidate);
+                                    break 'search;
+                                }
+                            }
+                        }
+                    }
+                }
+                found
+            },
+        };
+        let welded = found.unwrap_or_else(|| {
+            let welded = vertices.len() as u32;
+            vertices.push(*corner);
+            match epsilon {
+                None => { exact.insert(attribute_key(corner), welded); },
+                Some(epsilon) => grid.entry(cell(corner, epsilon)).or_default().push(welded),
+            }
+            welded
+        });
+        remap[*index as usize] = Some(welded);
+        result.push(welded);
+    }
+    (vertices, result)
+}
+
+// Average cache miss ratio: vertices transformed per triangle with a FIFO
+// post-transform cache. 3.0 means no reuse, 0.5 is the best for large grids.
+pub fn acmr(indices: &[u32], cache_size: usize) -> f32 {
+    if indices.len() < 3 {
+        return 0.0
+    }
+    let mut cache = std::collections::VecDeque::with_capacity(cache_size);
+    let mut misses = 0;
+    for index in indices {
+        if !cache.contains(index) {
+            misses += 1;
+            if cache.len() == cache_size {
+                cache.pop_front();
+            }
+            cache.push_back(*index);
+        }
+    }
+    misses as f32 / (indices.len() / 3) as f32
+}
+
+fn vertex_score(cache_position: Option<usize>, remaining: usize) -> f32 {
+    if remaining == 0 {
+        return -1.0
+    }
+    let cache_score = match cache_position {
+        None => 0.0,
+        Some(position) if position < 3 => LAST_TRIANGLE_SCORE,
+        Some(position) => {
+            let t = 1.0 - (position - 3) as f32 / (CACHE_SIZE - 3) as f32;
+            t.powf(CACHE_DECAY_POWER)
+        },
+    };
+    cache_score + VALENCE_BOOST_SCALE * (remaining as f32).powf(-VALENCE_BOOST_POWER)
+}
+
+// Tom Forsyth's linear-speed vertex cache optimisation: triangles are
+// emitted greedily by the scores of their vertices in a simulated LRU cache.
+pub fn optimize_vertex_cache(indices: &[u32], vertex_count: usize) -> Vec<u32> {
+    let triangle_count = indices.len() / 3;
+    let mut adjacency: Vec<Vec<usize>> = vec![Vec::new(); vertex_count];
+    for t in 0..triangle_count {
+        for k in 0..3 {
+            adjacency[indices[t * 3 + k] as usize].push(t);
+        }
+    }
+    let mut scores: Vec<f32> = adjacency.iter().map(|a| vertex_score(None, a.len())).collect();
+    let triangle_score = |t: usize, scores: &[f32]| -> f32 {
+        (0..3).map(|k| scores[indices[t * 3 + k] as usize]).sum()
+    };
+    let mut emitted = vec![false; triangle_count];
+    let mut cache: Vec<u32> = Vec::with_capacity(CACHE_SIZE + 3);
+    let mut result = Vec::with_capacity(indices.len());
+    let mut next_unemitted = 0;
+    let mut best = (0..triangle_count)
+        .max_by(|a, b| triangle_score(*a, &scores).total_cmp(&triangle_score(*b, &scores)));
+    while let Some(t) = best {
+        emitted[t] = true;
+        let vertices = [indices[t * 3], indices[t * 3 + 1], indices[t * 3 + 2]];
+        result.extend(vertices);
+        for v in vertices {
+            adjacency[v as usize].retain(|a| *a != t);
+            cache.retain(|c| *c != v);
+        }
+        let mut front = vertices.to_vec();
+        front.dedup();
+        if front.len() == 3 && front[0] == front[2] {
+            front.pop();
+        }
+        cache.splice(0..0, front);
+        let evicted: Vec<u32> = if cache.len() > CACHE_SIZE { cache.split_off(CACHE_SIZE) } else { Vec::new() };
+        for v in &evicted {
+            scores[*v as usize] = vertex_score(None, adjacency[*v as usize].len());
+        }
+        for (position, v) in cache.iter().enumerate() {
+            scores[*v as usize] = vertex_score(Some(position), adjacency[*v as usize].len());
+        }
+        // The next triangle is the best one using a cached vertex, or the
+        // first one left when the cache has no triangles left.
+        best = cache.iter()
+            .flat_map(|v| adjacency[*v as usize].iter().copied())
+            .max_by(|a, b| triangle_score(*a, &scores).total_cmp(&triangle_score(*b, &scores)));
+        if best.is_none() {
+            while next_unemitted < triangle_count && emitted[next_unemitted] {
+                next_unemitted += 1;
+            }
+            best = (next_unemitted < triangle_count).then_some(next_unemitted);
+        }
+    }
+    result
+}
+
+// Renumbers the vertices in the order the triangles first use them.
+pub fn optimize_vertex_fetch(vertices: &[Vertex], indices: &[u32]) -> (Vec<Vertex>, Vec<u32>) {
+    let mut remap: Vec<Option<u32>> = vec![None; vertices.len()];
+    let mut ordered = Vec::with_capacity(vertices.len());
+    let indices = indices.iter()
+        .map(|index| {
+            *remap[*index as usize].get_or_insert_with(|| {
+                ordered.push(vertices[*index as usize]);
+                ordered.len() as u32 - 1
+            })
+        })
+        .collect();
+    (ordered, indices)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mat::{Vec2d, Vec3d, Vec4d};
+    use crate::model::fixtures::grid;
+
+    fn vertex(x: f32, y: f32) -> Vertex {
+        let zero = Vec3d::new(0.0, 0.0, 0.0);
+        Vertex {
+            position: Vec3d::new(x, y, 0.0),
+            color: zero,
+            uv: Vec2d::new(0.0, 0.0),
+            normal: zero,
+            tangent: Vec4d::new(0.0, 0.0, 0.0, 0.0),
+        }
+    }
+
+    fn sorted_triangles(indices: &[u32]) -> Vec<[u32; 3]> {
+        let mut triangles: Vec<[u32; 3]> = indices.chunks(3).map(|t| [t[0], t[1], t[2]]).collect();
+        triangles.sort();
+        triangles
+    }
+
+    #[test]
+    fn weld_merges_equal_corners() {
+        let corners = [vertex(0.0, 0.0), vertex(1.0, 0.0), vertex(0.0, 1.0), vertex(1.0, 0.0), vertex(1.0, 1.0), vertex(0.0, 1.0)];
+        let (vertices, indices) = weld(&corners, &[0, 1, 2, 3, 4, 5], None);
+        assert_eq!(vertices.len(), 4);
+        assert_eq!(indices, vec![0, 1, 2, 1, 3, 2]);
+    }
+
+    #[test]
+    fn weld_with_epsilon_merges_close_positions() {
+        let corners = [vertex(0.0, 0.0), vertex(0.001, 0.0), vertex(1.0, 0.0)];
+        assert_eq!(weld(&corners, &[0, 1, 2], None).0.len(), 3);
+        let (vertices, indices) = weld(&corners, &[0, 1, 2], Some(0.01));
+        assert_eq!(vertices.len(), 2);
+        assert_eq!(indices, vec![0, 0, 1]);
+    }
+
+    #[test]
+    fn acmr_counts_misses_per_triangle() {
+        assert_eq!(acmr(&[0, 1, 2, 3, 4, 5], 16), 3.0);
+        assert_eq!(acmr(&[0, 1, 2, 2, 1, 3], 16), 2.0);
+        assert_eq!(acmr(&[0, 1, 2, 3, 4, 5, 0, 1, 2], 3), 3.0);
+    }
+
+    #[test]
+    fn optimized_grid_misses_less() {
+        let (_, indices, stats) = grid(32).optimized_buffers();
+        assert_eq!(stats.triangles, 32 * 32 * 2);
+        assert!(stats.acmr_after <= stats.acmr_before, "{} > {}", stats.acmr_after, stats.acmr_before);
+        assert!(stats.acmr_after < 1.0, "{}", stats.acmr_after);
+        assert_eq!(acmr(&indices, ACMR_CACHE_SIZE), stats.acmr_after);
+    }
+
+    #[test]
+    fn vertex_cache_order_keeps_the_triangles() {
+        let model = grid(8);
+        let indices: Vec<u32> = model.faces().iter()
+            .flat_map(|f| [f[0], f[1], f[2], f[0], f[2], f[3]])
+            .map(|i| i as u32)
+            .collect();
+        let optimized = optimize_vertex_cache(&indices, model.get_vertices().len());
+        assert_eq!(sorted_triangles(&optimized), sorted_triangles(&indices));
+    }
+}
@@ -0,0 +1,355 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use super::Model;
+use super::normal::position_key;
+use super::stats::DEGENERATE_AREA;
+
+
+pub const DEFAULT_MAX_HOLE_EDGES: usize = 8;
+
+
+#[derive(Debug, Clone, Default)]
+pub struct RepairReport {
+    pub repeated_corners: usize,
+    pub degenerate_faces: usize,
+    pub duplicate_faces: usize,
+    pub components: usize,
+    pub non_orientable_components: usize,
+    pub flipped_faces: usize,
+    pub filled_holes: usize,
+    pub fill_triangles: usize,
+    pub open_holes: usize,
+}
+
+impl Model {
+    // Works on the faces as loaded, before `resolve_duplicate_indices`;
+    // the vertices are kept, so are their colors, UVs and normals. Faces are
+    // compared by position, like in the stats. Boundary loops of at most
+    // `max_hole_edges` edges are filled, 0 fills none.
+    pub fn repair(&mut self, max_hole_edges: usize) -> RepairReport {
+        let mut report = RepairReport::default();
+        let positions: Vec<usize> = {
+            let mut ids: HashMap<[u32; 3], usize> = HashMap::new();
+            self.vertices.iter()
+                .map(|v| {
+                    let next = ids.len();
+                    *ids.entry(position_key(v)).or_insert(next)
+                })
+                .collect()
+        };
+        let (min, max) = self.bounding_box();
+        let size = max.minus(&min);
+        let largest = size.d0.max(size.d1).max(size.d2);
+
+        let mut faces = Vec::new();
+        for face in self.faces() {
+            let mut corners: Vec<usize> = Vec::with_capacity(face.len());
+            for c in &face {
+                if corners.last().map_or(false, |l| positions[*l] == positions[*c]) {
+                    continue;
+                }
+                corners.push(*c);
+            }
+            while corners.len() > 1 && positions[corners[0]] == positions[corners[corners.len() - 1]] {
+                corners.pop();
+            }
+            report.repeated_corners += face.len() - corners.len();
+            let distinct = corners.iter().map(|c| positions[*c]).collect::<HashSet<_>>().len();
+            let area = self.newell_normal(&corners).length() / 2.0;
+            if distinct < 3 || distinct != corners.len() || area <= DEGENERATE_AREA * largest * largest {
+                report.degenerate_faces += 1;
+                continue;
+            }
+            faces.push(corners);
+        }
+
+        let mut seen = HashSet::new();
+        faces.retain(|face| {
+            let mut key: Vec<usize> = face.iter().map(|c| positions[*c]).collect();
+            key.sort_unstable();
+            let first = seen.insert(key);
+            if !first {
+                report.duplicate_faces += 1;
+            }
+            first
+        });
+
+        // The holes are traced along consistently oriented faces. Filling
+        // them can close a component, which is then turned outward.
+        let first = self.orient(&mut faces, &positions, &mut report);
+        let loaded = faces.len();
+        if max_hole_edges > 0 {
+            self.fill_holes(&mut faces, &positions, max_hole_edges, &mut report);
+        }
+        let second = self.orient(&mut faces, &positions, &mut report);
+        report.flipped_faces = (0..loaded).filter(|f| first[*f] != second[*f]).count();
+        self.index_count = faces.iter().map(|f| f.len()).collect();
+        self.indices = faces.concat().into_iter().map(|c| c as gl::types::GLuint).collect();
+        report
+    }
+
+    // Neighbors across manifold edges must run the shared edge the other
+    // way. Each connected component is then turned outward when it is
+    // closed, or keeps the orientation of most of its faces. Returns the
+    // faces reversed.
+    fn orient(&self, faces: &mut [Vec<usize>], positions: &[usize], report: &mut RepairReport) -> Vec<bool> {
+        let face_positions: Vec<Vec<usize>> = faces.iter()
+            .map(|f| f.iter().map(|c| positions[*c]).collect())
+            .collect();
+        let edges = |f: usize| {
+            let face = &face_positions[f];
+            (0..face.len()).map(move |i| (face[i], face[(i + 1) % face.len()]))
+        };
+        let mut edge_faces: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for f in 0..faces.len() {
+            for (a, b) in edges(f) {
+                edge_faces.entry((a.min(b), a.max(b))).or_default().push(f);
+            }
+        }
+        let mut flip = vec![false; faces.len()];
+        let mut visited = vec![false; faces.len()];
+        report.components = 0;
+        report.non_orientable_components = 0;
+        for seed in 0..faces.len() {
+            if visited[seed] {
+                continue;
+            }
+            visited[seed] = true;
+            let mut component = Vec::new();
+            let mut queue = VecDeque::from([seed]);
+            let (mut closed, mut orientable) = (true, true);
+            while let Some(f) = queue.pop_front() {
+                component.push(f);
+                for (a, b) in edges(f) {
+                    let neighbors = &edge_faces[&(a.min(b), a.max(b))];
+                    let [g, h] = neighbors.as_slice() else {
+                        closed = false;
+                        continue;
+                    };
+                    let g = if *g == f { *h } else { *g };
+                    let same_way = edges(g).any(|e| e == (a, b));
+                    let flip_g = flip[f] ^ same_way;
+                    if !visited[g] {
+                        visited[g] = true;
+                        flip[g] = flip_g;
+                        queue.push_back(g);
+                    } else if flip[g] != flip_g {
+                        orientable = false;
+                    }
+                }
+            }
+            let invert = if closed && orientable {
+                let volume: f32 = component.iter()
+                    .map(|f| {
+                        let face = &faces[*f];
+                        let volume: f32 = (1..face.len() - 1)
+                            .map(|i| {
+                                let (a, b, c) = (self.vertices[face[0]], self.vertices[face[i]], self.vertices[face[i + 1]]);
+                                a.inner_product(&b.cross_product(&c)) / 6.0
+                            })
+                            .sum();
+                        if flip[*f] { -volume } else { volume }
+                    })
+                    .sum();
+                volume < 0.0
+            } else {
+                component.iter().filter(|f| flip[**f]).count() * 2 > component.len()
+            };
+            for f in &component {
+                flip[*f] ^= invert;
+            }
+            report.components += 1;
+            if !orientable {
+                report.non_orientable_components += 1;
+            }
+        }
+        for (face, flip) in faces.iter_mut().zip(&flip) {
+            if *flip {
+                face.reverse();
+            }
+        }
+        flip
+    }
+
+    // A boundary edge of a face is run the other way by its hole, so the
+    // filling faces agree with the faces around them.
+    fn fill_holes(&self, faces: &mut Vec<Vec<usize>>, positions: &[usize], max_hole_edges: usize, report: &mut RepairReport) {
+        let mut edges: HashMap<(usize, usize), usize> = HashMap::new();
+        for face in faces.iter() {
+            for i in 0..face.len() {
+                let (a, b) = (positions[face[i]], positions[face[(i + 1) % face.len()]]);
+                *edges.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+            }
+        }
+        // (from, to, corner at from) of each edge of the holes.
+        let mut hole_edges = Vec::new();
+        for face in faces.iter() {
+            for i in 0..face.len() {
+                let (ca, cb) = (face[i], face[(i + 1) % face.len()]);
+                let (a, b) = (positions[ca], positions[cb]);
+                if edges[&(a.min(b), a.max(b))] == 1 {
+                    hole_edges.push((b, a, cb));
+                }
+            }
+        }
+        let mut outgoing: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (e, (from, _, _)) in hole_edges.iter().enumerate() {
+            outgoing.entry(*from).or_default().push(e);
+        }
+        // Holes touching at a vertex are split there into simple loops.
+        let mut loops = Vec::new();
+        let mut used = vec![false; hole_edges.len()];
+        for start in 0..hole_edges.len() {
+            let (mut corners, mut path) = (Vec::new(), Vec::new());
+            let mut current = start;
+            while !used[current] {
+                used[current] = true;
+                let (from, to, corner) = hole_edges[current];
+                corners.push(corner);
+                path.push(from);
+                if let Some(k) = path.iter().position(|p| *p == to) {
+                    loops.push(corners.split_off(k));
+                    path.truncate(k);
+                }
+                match outgoing.get(&to).and_then(|edges| edges.iter().find(|e| !used[**e])) {
+                    Some(next) => current = *next,
+                    None => break,
+                }
+            }
+            if !path.is_empty() {
+                report.open_holes += 1;
+            }
+        }
+        for corners in loops {
+            if corners.len() < 3 || corners.len() > max_hole_edges {
+                report.open_holes += 1;
+                continue;
+            }
+            let triangles = self.triangulate(&corners, positions, &mut edges);
+            report.filled_holes += 1;
+            report.fill_triangles += triangles.len();
+            faces.extend(triangles.into_iter().map(|t| t.to_vec()));
+        }
+    }
+
+    // Clips the corner whose new edge is the shortest, which follows the
+    // hole in 3d. A new edge is not put over an existing one unless no
+    // other is left.
+    fn triangulate(&self, corners: &[usize], positions: &[usize], edges: &mut HashMap<(usize, usize), usize>) -> Vec<[usize; 3]> {
+        let key = |a: usize, b: usize| {
+            let (a, b) = (positions[a], positions[b]);
+            (a.min(b), a.max(b))
+        };
+        let mut remaining = corners.to_vec();
+        let mut triangles = Vec::with_capacity(corners.len() - 2);
+        while remaining.len() > 3 {
+            let n = remaining.len();
+            let clip = (0..n)
+                .min_by(|i, j| {
+                    let score = |i: usize| {
+                        let (p, q) = (remaining[(i + n - 1) % n], remaining[(i + 1) % n]);
+                        let length = self.vertices[p].minus(&self.vertices[q]).length();
+                        (edges.contains_key(&key(p, q)), length)
+                    };
+                    let (a, b) = (score(*i), score(*j));
+                    a.0.cmp(&b.0).then(a.1.total_cmp(&b.1))
+                })
+                .unwrap_or(0);
+            let (p, c, q) = (remaining[(clip + n - 1) % n], remaining[clip], remaining[(clip + 1) % n]);
+            triangles.push([p, c, q]);
+            *edges.entry(key(p, q)).or_insert(0) += 1;
+            remaining.remove(clip);
+        }
+        triangles.push([remaining[0], remaining[1], remaining[2]]);
+        triangles
+    }
+}
+
+impl RepairReport {
+    pub fn lines(&self) -> Vec<String> {
+        vec![
+            format!("repair: {} degenerate faces, {} duplicate faces removed, {} repeated corners dropped",
+                self.degenerate_faces, self.duplicate_faces, self.repeated_corners),
+            format!("repair: {} faces flipped in {} components ({} not orientable)",
+                self.flipped_faces, self.components, self.non_orientable_components),
+            format!("repair: {} holes filled with {} triangles, {} left open",
+                self.filled_holes, self.fill_triangles, self.open_holes),
+        ]
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mat::Vec3d;
+    use crate::model::fixtures::{cube, mesh};
+
+    // The cube with its faces changed.
+    fn cube_with(change: impl Fn(&mut Vec<Vec<usize>>)) -> Model {
+        let cube = cube();
+        let mut faces = cube.faces();
+        change(&mut faces);
+        let positions: Vec<[f32; 3]> = cube.vertices.iter().map(|v| [v.d0, v.d1, v.d2]).collect();
+        let faces: Vec<Vec<u32>> = faces.iter().map(|f| f.iter().map(|c| *c as u32).collect()).collect();
+        let faces: Vec<&[u32]> = faces.iter().map(|f| &f[..]).collect();
+        mesh(&positions, &faces)
+    }
+
+    fn assert_outward(model: &Model) {
+        let center = Vec3d::new(0.5, 0.5, 0.5);
+        for face in model.faces() {
+            let outward = model.vertices[face[0]].minus(&center);
+            assert!(model.newell_normal(&face).inner_product(&outward) > 0.0, "face {:?} points inward", face);
+        }
+        let stats = model.compute_stats();
+        assert!(stats.is_closed());
+        assert!((stats.volume - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn flipped_face_is_turned_back() {
+        let mut model = cube_with(|faces| faces[3].reverse());
+        let report = model.repair(DEFAULT_MAX_HOLE_EDGES);
+        assert_eq!(report.flipped_faces, 1);
+        assert_eq!(report.components, 1);
+        assert_outward(&model);
+    }
+
+    #[test]
+    fn inside_out_cube_is_turned_outward() {
+        let mut model = cube_with(|faces| faces.iter_mut().for_each(|f| f.reverse()));
+        let report = model.repair(DEFAULT_MAX_HOLE_EDGES);
+        assert_eq!(report.flipped_faces, 6);
+        assert_outward(&model);
+    }
+
+    #[test]
+    fn missing_face_is_filled() {
+        let mut model = cube_with(|faces| { faces.remove(1); });
+        let report = model.repair(DEFAULT_MAX_HOLE_EDGES);
+        assert_eq!((report.filled_holes, report.fill_triangles, report.open_holes), (1, 2, 0));
+        assert_eq!(model.compute_stats().euler_characteristic, 2);
+        assert_outward(&model);
+    }
+
+    #[test]
+    fn holes_stay_open_past_the_limit() {
+        let mut model = cube_with(|faces| { faces.remove(1); });
+        let report = model.repair(3);
+        assert_eq!((report.filled_holes, report.open_holes), (0, 1));
+        assert!(!model.compute_stats().is_closed());
+    }
+
+    #[test]
+    fn degenerate_and_duplicate_faces_are_removed() {
+        let mut model = cube_with(|faces| {
+            faces.push(faces[0].iter().rev().copied().collect());
+            faces.push(vec![0, 1, 1]);
+        });
+        let report = model.repair(DEFAULT_MAX_HOLE_EDGES);
+        assert_eq!((report.duplicate_faces, report.degenerate_faces), (1, 1));
+        assert_eq!(model.faces().len(), 6);
+        assert_outward(&model);
+    }
+}
@@ -19,7 +19,6 @@ impl Model {
         self.indices = vec![
             0, 1, 2, 0, 3, 2
         ];
-        self.max_size = 1.0;
         self.set_index_set(3);
         self.index_count = vec![3, 3]
     }
@@ -41,7 +40,6 @@ impl Model {
         self.indices = vec![
             0, 1, 2, 0, 3, 2
         ];
-        self.max_size = 1.0;
         self.set_index_set(3);
         self.index_count = vec![3, 3]
     }
@@ -124,7 +122,6 @@ impl Model {
             Vec3d::new(0.820,  0.883,  0.371),
             Vec3d::new(0.982,  0.099,  0.879)
         ];
-        self.max_size = 1.0;
     }
 
     #[allow(dead_code)]
@@ -205,6 +202,5 @@ impl Model {
             Vec2d::new(1.000004, 1.0-0.671847),
             Vec2d::new(0.667979, 1.0-0.335851)
         ];
-        self.max_size = 1.0;
     }
 }
\ No newline at end of file
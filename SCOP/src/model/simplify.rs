@@ -0,0 +1,473 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use super::Model;
+use crate::mat::Vec3d;
+
+
+// Penalty of moving off a boundary or a UV seam, relative to a face plane.
+const CONSTRAINT_WEIGHT: f64 = 10.0;
+// A collapse must not turn a face by more than about 80 degrees.
+const MIN_NORMAL_COS: f32 = 0.2;
+
+
+// Symmetric 4x4 error quadric of Garland and Heckbert, upper triangle only.
+#[derive(Debug, Clone, Copy, Default)]
+struct Quadric([f64; 10]);
+
+#[derive(Debug)]
+struct Triangle {
+    positions: [usize; 3],
+    // Model vertices giving the color and UV of each corner.
+    corners: [usize; 3],
+    removed: bool,
+}
+
+#[derive(Debug)]
+struct Collapse {
+    error: f64,
+    from: usize,
+    to: usize,
+    stamps: (u32, u32),
+}
+
+impl Quadric {
+    fn plane(normal: &Vec3d, point: &Vec3d, weight: f64) -> Self {
+        let (a, b, c) = (normal.d0 as f64, normal.d1 as f64, normal.d2 as f64);
+        let d = -(a * point.d0 as f64 + b * point.d1 as f64 + c * point.d2 as f64);
+        Self([a * a, a * b, a * c, a * d, b * b, b * c, b * d, c * c, c * d, d * d].map(|v| v * weight))
+    }
+
+    fn add(&self, rhs: &Self) -> Self {
+        let mut q = self.0;
+        for (v, r) in q.iter_mut().zip(rhs.0) {
+            *v += r;
+        }
+        Self(q)
+    }
+
+    fn error(&self, p: &Vec3d) -> f64 {
+        let (x, y, z) = (p.d0 as f64, p.d1 as f64, p.d2 as f64);
+        let q = &self.0;
+        (q[0] * x * x + 2.0 * q[1] * x * y + 2.0 * q[2] * x * z + 2.0 * q[3] * x
+            + q[4] * y * y + 2.0 * q[5] * y * z + 2.0 * q[6] * y
+            + q[7] * z * z + 2.0 * q[8] * z
+            + q[9]).max(0.0)
+    }
+}
+
+// Reversed, so the binary heap pops the smallest error first.
+impl Ord for Collapse {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.error.total_cmp(&self.error)
+    }
+}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Collapse {}
+
+struct Simplifier<'a> {
+    model: &'a Model,
+    positions: Vec<Vec3d>,
+    quadrics: Vec<Quadric>,
+    triangles: Vec<Triangle>,
+    adjacency: Vec<Vec<usize>>,
+    alive: Vec<bool>,
+    stamps: Vec<u32>,
+    heap: BinaryHeap<Collapse>,
+    wedges: Vec<[u32; 2]>,
+    triangle_count: usize,
+}
+
+impl Model {
+    // Edge collapses ordered by quadric error, until at most `target`
+    // triangles are left or the next collapse would move the surface further
+    // than `max_error` times the model size. Vertices collapse onto one end
+    // of their edge so the kept corners keep their colors and UVs; boundaries
+    // only collapse along themselves, and so do the seams of UVs set on the
+    // model (the loader does not read `vt`, so only the built-in cube has
+    // any). Normals are computed again.
+    pub fn simplify(&self, target: usize, max_error: Option<f32>) -> Model {
+        let mut simplifier = Simplifier::new(self);
+        let (min, max) = self.bounding_box();
+        let extent = max.minus(&min);
+        let size = extent.d0.max(extent.d1).max(extent.d2) as f64;
+        let max_error = max_error.map(|e| (e as f64 * size).powi(2)).unwrap_or(f64::MAX);
+        simplifier.run(target, max_error);
+        simplifier.into_model()
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.faces().iter().map(|f| f.len().saturating_sub(2)).sum()
+    }
+}
+
+impl<'a> Simplifier<'a> {
+    fn new(model: &'a Model) -> Self {
+        let mut position_ids: HashMap<[u32; 3], usize> = HashMap::new();
+        let mut positions = Vec::new();
+        let corner_positions: Vec<usize> = model.vertices.iter()
+            .map(|v| {
+                let key = [v.d0, v.d1, v.d2].map(|x| (x + 0.0).to_bits());
+                *position_ids.entry(key).or_insert_with(|| {
+                    positions.push(*v);
+                    positions.len() - 1
+                })
+            })
+            .collect();
+        // Only UVs given with the model make seams, the others are computed
+        // again for the simplified faces.
+        let wedges = (0..model.vertices.len())
+            .map(|i| {
+                let uv = model.source_uv.get(i).map(|t| [t.d0, t.d1]).unwrap_or_default();
+                uv.map(|x| (x + 0.0).to_bits())
+            })
+            .collect();
+        let mut triangles = Vec::new();
+        for face in model.faces() {
+            for i in 1..face.len().saturating_sub(1) {
+                let corners = [face[0], face[i], face[i + 1]];
+                let p = corners.map(|c| corner_positions[c]);
+                if p[0] != p[1] && p[1] != p[2] && p[2] != p[0] {
+                    triangles.push(Triangle { positions: p, corners, removed: false });
+                }
+            }
+        }
+        let mut adjacency = vec![Vec::new(); positions.len()];
+        for (t, triangle) in triangles.iter().enumerate() {
+            for p in triangle.positions {
+                adjacency[p].push(t);
+            }
+        }
+        let mut simplifier = Self {
+            model,
+            quadrics: vec![Quadric::default(); positions.len()],
+            alive: vec![true; positions.len()],
+            stamps: vec![0; positions.len()],
+            triangle_count: triangles.len(),
+            positions,
+            triangles,
+            adjacency,
+            heap: BinaryHeap::new(),
+            wedges,
+        };
+        simplifier.init_quadrics();
+        for v in 0..simplifier.positions.len() {
+            simplifier.push_edges(v);
+        }
+        simplifier
+    }
+
+    fn init_quadrics(&mut self) {
+        for triangle in &self.triangles {
+            let p = triangle.positions.map(|i| self.positions[i]);
+            let normal = face_normal(&p);
+            if normal.length() == 0.0 {
+                continue;
+            }
+            let normal = normal.normalize();
+            let quadric = Quadric::plane(&normal, &p[0], 1.0);
+            for i in triangle.positions {
+                self.quadrics[i] = self.quadrics[i].add(&quadric);
+            }
+        }
+        // A plane through each constrained edge, perpendicular to its faces,
+        // holds the vertices on the line.
+        for t in 0..self.triangles.len() {
+            let positions = self.triangles[t].positions;
+            let normal = face_normal(&positions.map(|i| self.positions[i]));
+            for k in 0..3 {
+                let (a, b) = (positions[k], positions[(k + 1) % 3]);
+                if !self.is_constrained(a, b) {
+                    continue;
+                }
+                let side = self.positions[b].minus(&self.positions[a]).cross_product(&normal);
+                if side.length() == 0.0 {
+                    continue;
+                }
+                let quadric = Quadric::plane(&side.normalize(), &self.positions[a], CONSTRAINT_WEIGHT);
+                self.quadrics[a] = self.quadrics[a].add(&quadric);
+                self.quadrics[b] = self.quadrics[b].add(&quadric);
+            }
+        }
+    }
+
+    fn live_triangles(&self, v: usize) -> impl Iterator<Item = usize> + '_ {
+        self.adjacency[v].iter().copied().filter(|t| !self.triangles[*t].removed)
+    }
+
+    fn edge_triangles(&self, a: usize, b: usize) -> Vec<usize> {
+        self.live_triangles(a).filter(|t| self.triangles[*t].positions.contains(&b)).collect()
+    }
+
+    fn neighbours(&self, v: usize) -> HashSet<usize> {
+        self.live_triangles(v)
+            .flat_map(|t| self.triangles[t].positions)
+            .filter(|p| *p != v)
+            .collect()
+    }
+
+    fn corner(&self, t: usize, v: usize) -> Option<usize> {
+        let triangle = &self.triangles[t];
+        (0..3).find(|k| triangle.positions[*k] == v).map(|k| triangle.corners[k])
+    }
+
+    // Boundary, non-manifold, or a seam where the two faces disagree on the
+    // attributes of an end.
+    fn is_constrained(&self, a: usize, b: usize) -> bool {
+        let faces = self.edge_triangles(a, b);
+        if faces.len() != 2 {
+            return true
+        }
+        [a, b].iter().any(|v| {
+            let wedges: Vec<_> = faces.iter()
+                .filter_map(|t| self.corner(*t, *v))
+                .map(|c| self.wedges[c])
+                .collect();
+            wedges[0] != wedges[1]
+        })
+    }
+
+    fn constrained_edges(&self, v: usize) -> usize {
+        self.neighbours(v).into_iter().filter(|n| self.is_constrained(v, *n)).count()
+    }
+
+    fn push_edges(&mut self, v: usize) {
+        for n in self.neighbours(v) {
+            for (from, to) in [(v, n), (n, v)] {
+                let error = self.quadrics[from].add(&self.quadrics[to]).error(&self.positions[to]);
+                self.heap.push(Collapse { error, from, to, stamps: (self.stamps[from], self.stamps[to]) });
+            }
+        }
+    }
+
+    fn can_collapse(&self, from: usize, to: usize) -> bool {
+        let edge = self.edge_triangles(from, to);
+        // The last triangles are never collapsed away.
+        if edge.is_empty() || edge.len() >= self.triangle_count {
+            return false
+        }
+        // A vertex on a boundary or a seam only slides along it.
+        let constrained = self.constrained_edges(from);
+        if constrained > 0 && (constrained != 2 || !self.is_constrained(from, to)) {
+            return false
+        }
+        if !self.satisfies_link(&edge, from, to) || self.folds(&edge, from, to) {
+            return false
+        }
+        self.live_triangles(from)
+            .filter(|t| !edge.contains(t))
+            .all(|t| {
+                let before = self.triangles[t].positions.map(|i| self.positions[i]);
+                let after = self.triangles[t].positions
+                    .map(|i| if i == from { self.positions[to] } else { self.positions[i] });
+                let (before, after) = (face_normal(&before), face_normal(&after));
+                if after.length() == 0.0 || before.length() == 0.0 {
+                    return false
+                }
+                before.normalize().inner_product(&after.normalize()) >= MIN_NORMAL_COS
+            })
+    }
+
+    // The link condition: the ends only share the vertices opposite the
+    // edge, otherwise the collapse pinches the surface.
+    fn satisfies_link(&self, edge: &[usize], from: usize, to: usize) -> bool {
+        let opposite: HashSet<usize> = edge.iter()
+            .flat_map(|t| self.triangles[*t].positions)
+            .filter(|p| *p != from && *p != to)
+            .collect();
+        let shared: HashSet<usize> = self.neighbours(from).intersection(&self.neighbours(to)).copied().collect();
+        shared == opposite
+    }
+
+    // Around a tetrahedron the link condition holds, but the triangles left
+    // fold onto the ones of `to`.
+    fn folds(&self, edge: &[usize], from: usize, to: usize) -> bool {
+        let sorted = |mut p: [usize; 3]| {
+            p.sort_unstable();
+            p
+        };
+        let kept: HashSet<[usize; 3]> = self.live_triangles(to)
+            .filter(|t| !edge.contains(t))
+            .map(|t| sorted(self.triangles[t].positions))
+            .collect();
+        self.live_triangles(from)
+            .filter(|t| !edge.contains(t))
+            .any(|t| kept.contains(&sorted(self.triangles[t].positions.map(|i| if i == from { to } else { i }))))
+    }
+
+    fn collapse(&mut self, from: usize, to: usize) {
+        let edge = self.edge_triangles(from, to);
+        for t in &edge {
+            self.triangles[*t].removed = true;
+        }
+        self.triangle_count -= edge.len();
+        let moved: Vec<usize> = self.live_triangles(from).collect();
+        for t in moved {
+            for k in 0..3 {
+                if self.triangles[t].positions[k] != from {
+                    continue;
+                }
+                // The attributes of `to` on the same side of any seam
+                // through `from`.
+                let wedge = self.wedges[self.triangles[t].corners[k]];
+                let corner = edge.iter()
+                    .find(|e| self.corner(**e, from).map(|c| self.wedges[c]) == Some(wedge))
+                    .and_then(|e| self.corner(*e, to));
+                if let Some(corner) = corner {
+                    self.triangles[t].corners[k] = corner;
+                }
+                self.triangles[t].positions[k] = to;
+            }
+            self.adjacency[to].push(t);
+        }
+        self.adjacency[to].retain(|t| !self.triangles[*t].removed);
+        self.adjacency[from].clear();
+        self.alive[from] = false;
+        self.quadrics[to] = self.quadrics[to].add(&self.quadrics[from]);
+        self.stamps[to] += 1;
+        self.push_edges(to);
+    }
+
+    fn run(&mut self, target: usize, max_error: f64) {
+        while self.triangle_count > target {
+            let Some(collapse) = self.heap.pop() else {
+                break;
+            };
+            let Collapse { error, from, to, stamps } = collapse;
+            if !self.alive[from] || !self.alive[to] || stamps != (self.stamps[from], self.stamps[to]) {
+                continue;
+            }
+            if error > max_error {
+                break;
+            }
+            if self.can_collapse(from, to) {
+                self.collapse(from, to);
+            }
+        }
+    }
+
+    // A triangle list with one vertex per corner, like the models after
+    // `resolve_duplicate_indices`.
+    fn into_model(self) -> Model {
+        let source = self.model;
+        let mut model = Model::new();
+        for triangle in self.triangles.iter().filter(|t| !t.removed) {
+            for (p, c) in triangle.positions.iter().zip(triangle.corners) {
+                model.vertices.push(self.positions[*p]);
+                if let Some(color) = source.colors.get(c) {
+                    model.colors.push(*color);
+                }
+                if let Some(uv) = source.source_uv.get(c) {
+                    model.source_uv.push(*uv);
+                }
+            }
+            model.index_count.push(3);
+        }
+        model.indices = (0..model.vertices.len() as gl::types::GLuint).collect();
+        model.index_set = 3;
+        model.weld_epsilon = source.weld_epsilon;
+        model.uv_projection = source.uv_projection;
+        model.set_uv();
+        if !source.normals.is_empty() {
+            model.set_normals(source.normal_mode, source.crease_angle);
+        }
+        model
+    }
+}
+
+fn face_normal(p: &[Vec3d; 3]) -> Vec3d {
+    p[1].minus(&p[0]).cross_product(&p[2].minus(&p[0]))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::fixtures::{grid, mesh};
+
+    fn triangles(model: &Model) -> Vec<[Vec3d; 3]> {
+        model.faces().iter().map(|f| [f[0], f[1], f[2]].map(|i| model.vertices[i])).collect()
+    }
+
+    fn area(model: &Model) -> f32 {
+        triangles(model).iter().map(|p| face_normal(p).length() / 2.0).sum()
+    }
+
+    fn has_position(model: &Model, x: f32, y: f32, z: f32) -> bool {
+        model.vertices.iter().any(|v| v.d0 == x && v.d1 == y && v.d2 == z)
+    }
+
+    // Three vertices around the equator, between a top and a bottom one.
+    fn bipyramid() -> Model {
+        mesh(
+            &[[0.0, 1.0, 0.0], [0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [-0.5, 0.0, -0.8], [-0.5, 0.0, 0.8]],
+            &[&[0, 2, 3], &[0, 3, 4], &[0, 4, 2], &[1, 3, 2], &[1, 4, 3], &[1, 2, 4]],
+        )
+    }
+
+    #[test]
+    fn flat_grid_collapses_to_two_triangles() {
+        let model = grid(4).simplify(2, None);
+        assert_eq!(model.triangle_count(), 2);
+        assert!(triangles(&model).iter().all(|p| face_normal(p).d2 > 0.0));
+        assert!((area(&model) - 16.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn open_boundary_keeps_its_outline() {
+        let model = grid(4).simplify(0, Some(1e-4));
+        assert!((area(&model) - 16.0).abs() < 1e-4);
+        for (x, y) in [(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)] {
+            assert!(has_position(&model, x, y, 0.0), "corner ({}, {}) moved", x, y);
+        }
+    }
+
+    #[test]
+    fn max_error_keeps_the_features() {
+        let mut model = grid(4);
+        model.vertices[2 * 5 + 2].d2 = 1.0;
+        let kept = model.simplify(0, Some(0.01));
+        assert!(has_position(&kept, 2.0, 2.0, 1.0));
+        let flattened = model.simplify(0, None);
+        assert!(!has_position(&flattened, 2.0, 2.0, 1.0));
+        assert!(kept.triangle_count() > flattened.triangle_count());
+    }
+
+    #[test]
+    fn smallest_error_collapses_first() {
+        let mut heap = BinaryHeap::new();
+        for error in [3.0, 1.0, 2.0] {
+            heap.push(Collapse { error, from: 0, to: 1, stamps: (0, 0) });
+        }
+        let order: Vec<f64> = std::iter::from_fn(|| heap.pop().map(|c| c.error)).collect();
+        assert_eq!(order, vec![1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn link_condition_rejects_pinching() {
+        let model = bipyramid();
+        let simplifier = Simplifier::new(&model);
+        // Two equator vertices also share the third one.
+        let equator = simplifier.edge_triangles(2, 3);
+        assert!(!simplifier.satisfies_link(&equator, 2, 3));
+        let side = simplifier.edge_triangles(0, 2);
+        assert!(simplifier.satisfies_link(&side, 0, 2));
+    }
+
+    #[test]
+    fn closed_mesh_stops_at_a_tetrahedron() {
+        assert_eq!(bipyramid().simplify(0, None).triangle_count(), 4);
+    }
+}
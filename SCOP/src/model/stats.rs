@@ -0,0 +1,224 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use super::Model;
+use super::normal::position_key;
+
+
+// Faces smaller than this ratio of the squared model size are degenerate.
+pub(super) const DEGENERATE_AREA: f32 = 1e-12;
+
+
+#[derive(Debug, Clone)]
+pub struct MeshStats {
+    pub vertices: usize,
+    pub positions: usize,
+    pub faces: usize,
+    pub triangles: usize,
+    pub edges: usize,
+    pub polygon_sizes: BTreeMap<usize, usize>,
+    pub area: f32,
+    pub volume: f32,
+    pub size: [f32; 3],
+    pub degenerate_faces: usize,
+    pub duplicate_vertices: usize,
+    pub boundary_edges: usize,
+    pub non_manifold_edges: usize,
+    pub boundary_loops: usize,
+    pub euler_characteristic: i64,
+}
+
+impl Model {
+    // Kept with the model, so they describe the mesh as loaded when taken
+    // before `resolve_duplicate_indices`.
+    pub fn update_stats(&mut self) -> &MeshStats {
+        self.stats.insert(self.compute_stats())
+    }
+
+    pub fn stats(&self) -> Option<&MeshStats> {
+        self.stats.as_ref()
+    }
+
+    // Vertices with the same position are counted once for the topology, so
+    // the stats are the same before and after `resolve_duplicate_indices`,
+    // except for the duplicates.
+    pub fn compute_stats(&self) -> MeshStats {
+        let faces = self.faces();
+        let mut position_ids: HashMap<[u32; 3], usize> = HashMap::new();
+        let vertex_positions: Vec<usize> = self.vertices.iter()
+            .map(|v| {
+                let next = position_ids.len();
+                *position_ids.entry(position_key(v)).or_insert(next)
+            })
+            .collect();
+        let (min, max) = self.bounding_box();
+        let size = max.minus(&min);
+        let largest = size.d0.max(size.d1).max(size.d2);
+
+        let mut polygon_sizes = BTreeMap::new();
+        let mut edges: HashMap<(usize, usize), usize> = HashMap::new();
+        let mut used = HashSet::new();
+        let (mut area, mut volume, mut degenerate_faces) = (0.0, 0.0, 0);
+        for face in &faces {
+            *polygon_sizes.entry(face.len()).or_insert(0) += 1;
+            let positions: Vec<usize> = face.iter().map(|c| vertex_positions[*c]).collect();
+            for (i, a) in positions.iter().enumerate() {
+                let b = positions[(i + 1) % positions.len()];
+                used.insert(*a);
+                if *a != b {
+                    *edges.entry((*a.min(&b), *a.max(&b))).or_insert(0) += 1;
+                }
+            }
+            let face_area = self.newell_normal(face).length() / 2.0;
+            let repeated = positions.iter().collect::<HashSet<_>>().len() != positions.len();
+            if repeated || face_area <= DEGENERATE_AREA * largest * largest {
+                degenerate_faces += 1;
+            }
+            area += face_area;
+            // Divergence theorem over the fan triangles.
+            for i in 1..face.len().saturating_sub(1) {
+                let (a, b, c) = (self.vertices[face[0]], self.vertices[face[i]], self.vertices[face[i + 1]]);
+                volume += a.inner_product(&b.cross_product(&c)) / 6.0;
+            }
+        }
+
+        let boundary: Vec<(usize, usize)> = edges.iter().filter(|(_, n)| **n == 1).map(|(e, _)| *e).collect();
+        let non_manifold_edges = edges.values().filter(|n| **n > 2).count();
+        let triangles = faces.iter().map(|f| f.len().saturating_sub(2)).sum();
+        MeshStats {
+            vertices: self.vertices.len(),
+            positions: position_ids.len(),
+            faces: faces.len(),
+            triangles,
+            edges: edges.len(),
+            polygon_sizes,
+            area,
+            volume,
+            size: [size.d0, size.d1, size.d2],
+            degenerate_faces,
+            duplicate_vertices: self.vertices.len() - position_ids.len(),
+            boundary_edges: boundary.len(),
+            non_manifold_edges,
+            boundary_loops: connected_components(&boundary),
+            euler_characteristic: used.len() as i64 - edges.len() as i64 + faces.len() as i64,
+        }
+    }
+}
+
+impl MeshStats {
+    pub fn is_closed(&self) -> bool {
+        self.boundary_edges == 0 && self.non_manifold_edges == 0
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        let polygons: Vec<String> = self.polygon_sizes.iter()
+            .map(|(size, count)| format!("{}-gon {}", size, count))
+            .collect();
+        vec![
+            format!("vertices: {} ({} positions, {} duplicates)", self.vertices, self.positions, self.duplicate_vertices),
+            format!("faces: {} ({} triangles)", self.faces, self.triangles),
+            format!("edges: {}", self.edges),
+            format!("polygons: {}", polygons.join(", ")),
+            format!("size: {:.4} x {:.4} x {:.4}", self.size[0], self.size[1], self.size[2]),
+            format!("area: {:.6}", self.area),
+            format!("volume: {:.6}{}", self.volume, if self.is_closed() { "" } else { " (not closed)" }),
+            format!("degenerate faces: {}", self.degenerate_faces),
+            format!("boundary edges: {} in {} loops", self.boundary_edges, self.boundary_loops),
+            format!("non-manifold edges: {}", self.non_manifold_edges),
+            format!("euler characteristic: {}", self.euler_characteristic),
+        ]
+    }
+}
+
+// Union-find over the edge ends.
+fn connected_components(edges: &[(usize, usize)]) -> usize {
+    let mut parent: HashMap<usize, usize> = HashMap::new();
+    fn find(parent: &mut HashMap<usize, usize>, v: usize) -> usize {
+        let mut root = v;
+        while let Some(p) = parent.get(&root).copied().filter(|p| *p != root) {
+            root = p;
+        }
+        let mut v = v;
+        while v != root {
+            let next = parent.insert(v, root).unwrap_or(root);
+            v = next;
+        }
+        root
+    }
+    for (a, b) in edges {
+        let (ra, rb) = (find(&mut parent, *a), find(&mut parent, *b));
+        if ra != rb {
+            parent.insert(ra, rb);
+        }
+    }
+    let vertices: Vec<usize> = edges.iter().flat_map(|(a, b)| [*a, *b]).collect::<HashSet<_>>().into_iter().collect();
+    vertices.into_iter().filter(|v| find(&mut parent, *v) == *v).count()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::fixtures::{cube, grid, mesh};
+
+    // `n` x `n` quads around a torus, the grid wrapped on both sides.
+    fn torus(n: usize) -> Model {
+        let (big, small) = (2.0f32, 0.5f32);
+        let step = std::f32::consts::TAU / n as f32;
+        let positions: Vec<[f32; 3]> = (0..n)
+            .flat_map(|i| (0..n).map(move |j| {
+                let (a, b) = (i as f32 * step, j as f32 * step);
+                let r = big + small * b.cos();
+                [r * a.cos(), small * b.sin(), r * a.sin()]
+            }))
+            .collect();
+        let at = |i: usize, j: usize| ((i % n) * n + j % n) as u32;
+        let quads: Vec<[u32; 4]> = (0..n)
+            .flat_map(|i| (0..n).map(move |j| [at(i, j), at(i, j + 1), at(i + 1, j + 1), at(i + 1, j)]))
+            .collect();
+        let faces: Vec<&[u32]> = quads.iter().map(|q| &q[..]).collect();
+        mesh(&positions, &faces)
+    }
+
+    // Genus of a closed orientable surface.
+    fn genus(stats: &MeshStats) -> i64 {
+        (2 - stats.euler_characteristic) / 2
+    }
+
+    #[test]
+    fn cube_is_a_closed_sphere() {
+        let stats = cube().compute_stats();
+        assert_eq!((stats.positions, stats.edges, stats.faces), (8, 12, 6));
+        assert_eq!(stats.euler_characteristic, 2);
+        assert_eq!(genus(&stats), 0);
+        assert!(stats.is_closed());
+        assert!((stats.area - 6.0).abs() < 1e-5);
+        assert!((stats.volume - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn topology_ignores_duplicate_vertices() {
+        let mut model = cube();
+        model.resolve_duplicate_indices();
+        let stats = model.compute_stats();
+        assert_eq!(stats.duplicate_vertices, 24 - 8);
+        assert_eq!(stats.euler_characteristic, 2);
+        assert!(stats.is_closed());
+    }
+
+    #[test]
+    fn torus_has_genus_one() {
+        let stats = torus(8).compute_stats();
+        assert!(stats.is_closed());
+        assert_eq!(stats.euler_characteristic, 0);
+        assert_eq!(genus(&stats), 1);
+    }
+
+    #[test]
+    fn grid_is_a_disc_with_one_boundary() {
+        let stats = grid(3).compute_stats();
+        assert_eq!(stats.euler_characteristic, 1);
+        assert_eq!((stats.boundary_edges, stats.boundary_loops), (12, 1));
+        assert!(!stats.is_closed());
+        assert_eq!(stats.degenerate_faces, 0);
+    }
+}
@@ -0,0 +1,418 @@
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use std::str::FromStr;
+
+use super::Model;
+use super::normal::position_key;
+use super::uv::UvProjection;
+use crate::mat::{Vec2d, Vec3d};
+
+
+// Levels are not built past this one, nor past this many triangles.
+const MAX_SUBDIVISION_LEVEL: usize = 5;
+const MAX_SUBDIVISION_TRIANGLES: usize = 2_000_000;
+
+
+// `Auto` uses Loop on triangle meshes and Catmull-Clark on the others.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SubdivisionScheme {
+    Auto,
+    CatmullClark,
+    Loop,
+}
+
+impl FromStr for SubdivisionScheme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(SubdivisionScheme::Auto),
+            "catmull-clark" => Ok(SubdivisionScheme::CatmullClark),
+            "loop" => Ok(SubdivisionScheme::Loop),
+            _ => Err(format!("error: unknown subdivision scheme: {}", s)),
+        }
+    }
+}
+
+impl SubdivisionScheme {
+    // Auto is Loop for triangle meshes, Catmull-Clark for the others.
+    fn resolve(self, faces: &[Vec<usize>]) -> Self {
+        match self {
+            SubdivisionScheme::Auto if faces.iter().all(|f| f.len() == 3) => SubdivisionScheme::Loop,
+            SubdivisionScheme::Auto => SubdivisionScheme::CatmullClark,
+            scheme => scheme,
+        }
+    }
+}
+
+// The faces on welded positions. Boundary and non-manifold edges are sharp,
+// and so are the edges between faces further apart than the crease angle.
+struct Topology {
+    positions: Vec<Vec3d>,
+    corner_positions: Vec<usize>,
+    faces: Vec<Vec<usize>>,
+    edge_ids: HashMap<(usize, usize), usize>,
+    edges: Vec<(usize, usize)>,
+    edge_faces: Vec<Vec<usize>>,
+    sharp: Vec<bool>,
+    vertex_edges: Vec<Vec<usize>>,
+    vertex_faces: Vec<Vec<usize>>,
+}
+
+impl Model {
+    // One level, as a new model with every corner its own vertex, like after
+    // `resolve_duplicate_indices`. Colors and UVs are interpolated inside
+    // each face, so UV seams stay where they are. Normals are computed again.
+    pub fn subdivide(&self, scheme: SubdivisionScheme, crease_angle: Option<f32>) -> Model {
+        let mut faces = self.faces();
+        let scheme = scheme.resolve(&faces);
+        if scheme == SubdivisionScheme::Loop {
+            faces = faces.iter()
+                .flat_map(|f| (1..f.len().saturating_sub(1)).map(|i| vec![f[0], f[i], f[i + 1]]))
+                .collect();
+        }
+        faces.retain(|f| f.len() >= 3);
+        let topology = Topology::new(self, faces, crease_angle);
+
+        let uv = if !self.source_uv.is_empty() {
+            self.source_uv.clone()
+        } else if self.uv_projection == UvProjection::Faces && self.uv.len() == self.vertices.len() {
+            self.uv.clone()
+        } else {
+            self.default_uv()
+        };
+        let colors = if self.colors.len() == self.vertices.len() { Some(&self.colors) } else { None };
+        let mut model = Model::new();
+        let mut push = |position: Vec3d, corners: &[usize]| {
+            model.vertices.push(position);
+            let weight = 1.0 / corners.len() as f32;
+            if let Some(colors) = colors {
+                let sum = corners.iter().fold(Vec3d::new(0.0, 0.0, 0.0), |s, c| s.add(&colors[*c]));
+                model.colors.push(sum.mul(weight));
+            }
+            let (u, v) = corners.iter().fold((0.0, 0.0), |(u, v), c| (u + uv[*c].d0, v + uv[*c].d1));
+            model.source_uv.push(Vec2d::new(u * weight, v * weight));
+        };
+
+        let index_set = match scheme {
+            SubdivisionScheme::Loop => {
+                let (vertex_points, edge_points) = topology.loop_points();
+                for face in &topology.faces {
+                    let [a, b, c] = [face[0], face[1], face[2]];
+                    let v = |corner: usize| (vertex_points[topology.corner_positions[corner]], vec![corner]);
+                    let e = |x: usize, y: usize| (edge_points[topology.edge(x, y)], vec![x, y]);
+                    let (ab, bc, ca) = (e(a, b), e(b, c), e(c, a));
+                    for triangle in [[v(a), ab.clone(), ca.clone()], [ab.clone(), v(b), bc.clone()], [ca.clone(), bc.clone(), v(c)], [ab, bc, ca]] {
+                        for (position, corners) in triangle {
+                            push(position, &corners);
+                        }
+                        model.index_count.push(3);
+                    }
+                }
+                3
+            },
+            _ => {
+                let (vertex_points, edge_points, face_points) = topology.catmull_clark_points();
+                for (f, face) in topology.faces.iter().enumerate() {
+                    let n = face.len();
+                    for i in 0..n {
+                        let (previous, corner, next) = (face[(i + n - 1) % n], face[i], face[(i + 1) % n]);
+                        push(vertex_points[topology.corner_positions[corner]], &[corner]);
+                        push(edge_points[topology.edge(corner, next)], &[corner, next]);
+                        push(face_points[f], face);
+                        push(edge_points[topology.edge(previous, corner)], &[previous, corner]);
+                        model.index_count.push(4);
+                    }
+                }
+                4
+            },
+        };
+        model.indices = (0..model.vertices.len() as gl::types::GLuint).collect();
+        model.index_set = index_set;
+        model.weld_epsilon = self.weld_epsilon;
+        model.uv_projection = self.uv_projection;
+        model.set_uv();
+        if !self.normals.is_empty() {
+            model.set_normals(self.normal_mode, self.crease_angle);
+        }
+        model
+    }
+
+    // Levels already built are kept; changing the scheme drops them.
+    pub fn set_subdivision(&mut self, scheme: SubdivisionScheme, crease_angle: Option<f32>) {
+        self.subdivision_scheme = scheme;
+        self.subdivision_crease = crease_angle;
+        self.subdivisions.clear();
+        self.subdivision_level = 0;
+    }
+
+    pub fn subdivision_level(&self) -> usize {
+        self.subdivision_level
+    }
+
+    // Builds the missing levels, each from the one before, and uploads them
+    // when the model buffers are set. Stops at the limits.
+    pub fn set_subdivision_level(&mut self, level: usize) {
+        while self.subdivisions.len() < level.min(MAX_SUBDIVISION_LEVEL) {
+            let previous = self.subdivisions.last().unwrap_or(self);
+            if previous.subdivided_triangle_count(self.subdivision_scheme) > MAX_SUBDIVISION_TRIANGLES {
+                break;
+            }
+            let mut next = previous.subdivide(self.subdivision_scheme, self.subdivision_crease);
+            if self.vao.is_some() {
+                next.set_buffers();
+                next.config_buffer();
+            }
+            self.subdivisions.push(next);
+        }
+        self.subdivision_level = level.min(self.subdivisions.len());
+    }
+
+    // Catmull-Clark makes a quad per corner, Loop four triangles per triangle
+    // of the fans.
+    fn subdivided_triangle_count(&self, scheme: SubdivisionScheme) -> usize {
+        let faces = self.faces();
+        let scheme = scheme.resolve(&faces);
+        faces.iter()
+            .filter(|f| f.len() >= 3)
+            .map(|f| match scheme {
+                SubdivisionScheme::Loop => 4 * (f.len() - 2),
+                _ => 2 * f.len(),
+            })
+            .sum()
+    }
+
+    pub fn drawn_triangle_count(&self) -> usize {
+        self.drawn().draw_count / 3
+    }
+
+    // Simplified levels only apply to the model as loaded.
+    pub(super) fn drawn(&self) -> &Model {
+        match self.subdivision_level {
+            0 => self.lod(),
+            level => &self.subdivisions[level - 1],
+        }
+    }
+}
+
+impl Topology {
+    fn new(model: &Model, faces: Vec<Vec<usize>>, crease_angle: Option<f32>) -> Self {
+        let mut position_ids: HashMap<[u32; 3], usize> = HashMap::new();
+        let mut positions = Vec::new();
+        let corner_positions: Vec<usize> = model.vertices.iter()
+            .map(|v| {
+                *position_ids.entry(position_key(v)).or_insert_with(|| {
+                    positions.push(*v);
+                    positions.len() - 1
+                })
+            })
+            .collect();
+        let mut topology = Self {
+            vertex_edges: vec![Vec::new(); positions.len()],
+            vertex_faces: vec![Vec::new(); positions.len()],
+            positions,
+            corner_positions,
+            faces: Vec::new(),
+            edge_ids: HashMap::new(),
+            edges: Vec::new(),
+            edge_faces: Vec::new(),
+            sharp: Vec::new(),
+        };
+        for (f, face) in faces.iter().enumerate() {
+            for (i, corner) in face.iter().enumerate() {
+                let a = topology.corner_positions[*corner];
+                let b = topology.corner_positions[face[(i + 1) % face.len()]];
+                let key = (a.min(b), a.max(b));
+                let next = topology.edges.len();
+                let e = *topology.edge_ids.entry(key).or_insert(next);
+                if e == next {
+                    topology.edges.push(key);
+                    topology.edge_faces.push(Vec::new());
+                    topology.vertex_edges[key.0].push(e);
+                    if key.1 != key.0 {
+                        topology.vertex_edges[key.1].push(e);
+                    }
+                }
+                topology.edge_faces[e].push(f);
+                if !topology.vertex_faces[a].contains(&f) {
+                    topology.vertex_faces[a].push(f);
+                }
+            }
+        }
+        let normals: Vec<Vec3d> = faces.iter().map(|f| model.newell_normal(f)).collect();
+        let cos_crease = crease_angle.map(|a| a.to_radians().cos());
+        topology.sharp = topology.edge_faces.iter()
+            .map(|faces| match faces.as_slice() {
+                [f, g] => cos_crease.map_or(false, |cos| {
+                    let (a, b) = (normals[*f], normals[*g]);
+                    let length = a.length() * b.length();
+                    length > 0.0 && a.inner_product(&b) / length < cos
+                }),
+                _ => true,
+            })
+            .collect();
+        topology.faces = faces;
+        topology
+    }
+
+    fn edge(&self, a: usize, b: usize) -> usize {
+        let (a, b) = (self.corner_positions[a], self.corner_positions[b]);
+        self.edge_ids[&(a.min(b), a.max(b))]
+    }
+
+    fn other_end(&self, e: usize, v: usize) -> usize {
+        let (a, b) = self.edges[e];
+        if a == v { b } else { a }
+    }
+
+    fn midpoint(&self, e: usize) -> Vec3d {
+        let (a, b) = self.edges[e];
+        self.positions[a].add(&self.positions[b]).mul(0.5)
+    }
+
+    // On a crease or a boundary, a vertex follows the curve of its two
+    // sharp edges; where more of them meet it is a corner and stays.
+    fn sharp_vertex(&self, v: usize) -> Option<Vec3d> {
+        let sharp: Vec<usize> = self.vertex_edges[v].iter().copied().filter(|e| self.sharp[*e]).collect();
+        let p = self.positions[v];
+        match sharp.as_slice() {
+            [] | [_] => None,
+            [a, b] => {
+                let (a, b) = (self.positions[self.other_end(*a, v)], self.positions[self.other_end(*b, v)]);
+                Some(p.mul(0.75).add(&a.add(&b).mul(0.125)))
+            },
+            _ => Some(p),
+        }
+    }
+
+    fn catmull_clark_points(&self) -> (Vec<Vec3d>, Vec<Vec3d>, Vec<Vec3d>) {
+        let face_points: Vec<Vec3d> = self.faces.iter()
+            .map(|face| {
+                let sum = face.iter().fold(Vec3d::new(0.0, 0.0, 0.0), |s, c| s.add(&self.positions[self.corner_positions[*c]]));
+                sum.mul(1.0 / face.len() as f32)
+            })
+            .collect();
+        let edge_points: Vec<Vec3d> = (0..self.edges.len())
+            .map(|e| match self.edge_faces[e].as_slice() {
+                [f, g] if !self.sharp[e] => {
+                    self.midpoint(e).mul(2.0).add(&face_points[*f]).add(&face_points[*g]).mul(0.25)
+                },
+                _ => self.midpoint(e),
+            })
+            .collect();
+        let vertex_points = (0..self.positions.len())
+            .map(|v| {
+                let p = self.positions[v];
+                let n = self.vertex_edges[v].len();
+                if let Some(point) = self.sharp_vertex(v) {
+                    return point
+                }
+                if n < 3 || self.vertex_faces[v].is_empty() {
+                    return p
+                }
+                let q = self.vertex_faces[v].iter()
+                    .fold(Vec3d::new(0.0, 0.0, 0.0), |s, f| s.add(&face_points[*f]))
+                    .mul(1.0 / self.vertex_faces[v].len() as f32);
+                let r = self.vertex_edges[v].iter()
+                    .fold(Vec3d::new(0.0, 0.0, 0.0), |s, e| s.add(&self.midpoint(*e)))
+                    .mul(1.0 / n as f32);
+                q.add(&r.mul(2.0)).add(&p.mul(n as f32 - 3.0)).mul(1.0 / n as f32)
+            })
+            .collect();
+        (vertex_points, edge_points, face_points)
+    }
+
+    fn loop_points(&self) -> (Vec<Vec3d>, Vec<Vec3d>) {
+        let opposite = |f: usize, e: usize| {
+            let (a, b) = self.edges[e];
+            self.faces[f].iter()
+                .map(|c| self.corner_positions[*c])
+                .find(|p| *p != a && *p != b)
+        };
+        let edge_points = (0..self.edges.len())
+            .map(|e| match self.edge_faces[e].as_slice() {
+                [f, g] if !self.sharp[e] => match (opposite(*f, e), opposite(*g, e)) {
+                    (Some(c), Some(d)) => self.midpoint(e).mul(0.75)
+                        .add(&self.positions[c].add(&self.positions[d]).mul(0.125)),
+                    _ => self.midpoint(e),
+                },
+                _ => self.midpoint(e),
+            })
+            .collect();
+        let vertex_points = (0..self.positions.len())
+            .map(|v| {
+                let p = self.positions[v];
+                let n = self.vertex_edges[v].len();
+                if let Some(point) = self.sharp_vertex(v) {
+                    return point
+                }
+                if n < 3 {
+                    return p
+                }
+                // Loop's original weights.
+                let c = 0.375 + 0.25 * (2.0 * PI / n as f32).cos();
+                let beta = (0.625 - c * c) / n as f32;
+                let sum = self.vertex_edges[v].iter()
+                    .fold(Vec3d::new(0.0, 0.0, 0.0), |s, e| s.add(&self.positions[self.other_end(*e, v)]));
+                p.mul(1.0 - n as f32 * beta).add(&sum.mul(beta))
+            })
+            .collect();
+        (vertex_points, edge_points)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::fixtures::{cube, tetrahedron};
+
+    fn assert_close(a: &Vec3d, b: [f32; 3]) {
+        assert!(a.minus(&Vec3d::new(b[0], b[1], b[2])).length() < 1e-5, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn catmull_clark_splits_a_cube_into_24_quads() {
+        let cube = cube();
+        let model = cube.subdivide(SubdivisionScheme::Auto, None);
+        let faces = model.faces();
+        assert_eq!(faces.len(), 24);
+        assert!(faces.iter().all(|f| f.len() == 4));
+        assert_eq!(model.triangle_count(), cube.subdivided_triangle_count(SubdivisionScheme::Auto));
+        let stats = model.compute_stats();
+        assert!(stats.is_closed());
+        assert_eq!(stats.euler_characteristic, 2);
+        // A corner moves to (Q + 2R) / 3 with three faces and edges around it.
+        assert_close(&model.vertices[faces[0][0]], [2.0 / 9.0; 3]);
+    }
+
+    #[test]
+    fn loop_splits_a_tetrahedron_into_16_triangles() {
+        let tetrahedron = tetrahedron();
+        let model = tetrahedron.subdivide(SubdivisionScheme::Auto, None);
+        let faces = model.faces();
+        assert_eq!(faces.len(), 16);
+        assert!(faces.iter().all(|f| f.len() == 3));
+        assert_eq!(model.triangle_count(), tetrahedron.subdivided_triangle_count(SubdivisionScheme::Auto));
+        let stats = model.compute_stats();
+        assert!(stats.is_closed());
+        assert_eq!(stats.euler_characteristic, 2);
+    }
+
+    #[test]
+    fn loop_on_quads_splits_their_fans() {
+        let model = cube().subdivide(SubdivisionScheme::Loop, None);
+        assert_eq!(model.triangle_count(), 6 * 2 * 4);
+        assert!(model.compute_stats().is_closed());
+    }
+
+    #[test]
+    fn subdivided_faces_keep_their_orientation() {
+        let model = cube().subdivide(SubdivisionScheme::CatmullClark, None);
+        let center = Vec3d::new(0.5, 0.5, 0.5);
+        for face in model.faces() {
+            let outward = model.vertices[face[0]].minus(&center);
+            assert!(model.newell_normal(&face).inner_product(&outward) > 0.0);
+        }
+    }
+}
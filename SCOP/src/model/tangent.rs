@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+use super::Model;
+use crate::mat::{Vec2d, Vec3d, Vec4d};
+
+
+// A triangle whose UVs cover less than this area gives no direction.
+const DEGENERATE_UV_AREA: f32 = 1e-12;
+
+
+impl Model {
+    // One tangent per vertex, approximately MikkTSpace-style: the tangent
+    // of each triangle is projected on the plane of the vertex normal,
+    // weighted by the angle of the corner, and summed over the corners with
+    // the same position, normal, UV and handedness. w is the sign of the
+    // bitangent, which the shader rebuilds as w * cross(normal, tangent).
+    pub fn tangents(&self) -> Vec<Vec4d> {
+        let uv = |i: usize| self.uv.get(i).copied().unwrap_or(Vec2d::new(0.0, 0.0));
+        let normal = |i: usize| {
+            let n = self.normals.get(i).copied().unwrap_or(Vec3d::new(0.0, 0.0, 0.0));
+            if n.length() > 0.0 { n.normalize() } else { Vec3d::new(0.0, 0.0, 1.0) }
+        };
+        let mut sums = vec![Vec3d::new(0.0, 0.0, 0.0); self.vertices.len()];
+        let mut signs = vec![1.0; self.vertices.len()];
+        for face in self.faces() {
+            // The handedness of a polygon is the one of its UV outline, so
+            // the triangles of a face never disagree.
+            let uv_area: f32 = (0..face.len())
+                .map(|i| {
+                    let (a, b) = (uv(face[i]), uv(face[(i + 1) % face.len()]));
+                    a.d0 * b.d1 - b.d0 * a.d1
+                })
+                .sum();
+            let sign = if uv_area < 0.0 { -1.0 } else { 1.0 };
+            for i in 1..face.len().saturating_sub(1) {
+                let triangle = [face[0], face[i], face[i + 1]];
+                let Some(tangent) = self.triangle_tangent(&triangle, &uv) else {
+                    continue;
+                };
+                for k in 0..3 {
+                    let corner = triangle[k];
+                    let n = normal(corner);
+                    let projected = tangent.minus(&n.mul(n.inner_product(&tangent)));
+                    if projected.length() == 0.0 {
+                        continue;
+                    }
+                    let weight = self.corner_angle(&triangle, k);
+                    sums[corner] = sums[corner].add(&projected.normalize().mul(weight));
+                    signs[corner] = sign;
+                }
+            }
+        }
+
+        let mut groups: HashMap<[u32; 9], Vec3d> = HashMap::new();
+        let key = |i: usize| {
+            let (p, n, t) = (self.vertices[i], normal(i), uv(i));
+            [p.d0, p.d1, p.d2, n.d0, n.d1, n.d2, t.d0, t.d1, signs[i]].map(|x| (x + 0.0).to_bits())
+        };
+        for (i, sum) in sums.iter().enumerate() {
+            let group = groups.entry(key(i)).or_insert(Vec3d::new(0.0, 0.0, 0.0));
+            *group = group.add(sum);
+        }
+        (0..self.vertices.len())
+            .map(|i| {
+                let n = normal(i);
+                let sum = groups[&key(i)];
+                let tangent = if sum.length() > 0.0 { sum.normalize() } else { perpendicular(&n) };
+                Vec4d::new(tangent.d0, tangent.d1, tangent.d2, signs[i])
+            })
+            .collect()
+    }
+
+    // The direction of increasing u on the triangle.
+    fn triangle_tangent(&self, triangle: &[usize; 3], uv: &impl Fn(usize) -> Vec2d) -> Option<Vec3d> {
+        let [a, b, c] = *triangle;
+        let (e1, e2) = (self.vertices[b].minus(&self.vertices[a]), self.vertices[c].minus(&self.vertices[a]));
+        let (ta, tb, tc) = (uv(a), uv(b), uv(c));
+        let (du1, dv1, du2, dv2) = (tb.d0 - ta.d0, tb.d1 - ta.d1, tc.d0 - ta.d0, tc.d1 - ta.d1);
+        let r = du1 * dv2 - du2 * dv1;
+        if r.abs() <= DEGENERATE_UV_AREA {
+            return None
+        }
+        let tangent = e1.mul(dv2).minus(&e2.mul(dv1)).mul(1.0 / r);
+        if tangent.length() > 0.0 { Some(tangent) } else { None }
+    }
+}
+
+// Any unit vector perpendicular to the normal, for the vertices without UVs.
+fn perpendicular(n: &Vec3d) -> Vec3d {
+    let axis = if n.d0.abs() < 0.9 { Vec3d::new(1.0, 0.0, 0.0) } else { Vec3d::new(0.0, 1.0, 0.0) };
+    axis.minus(&n.mul(n.inner_product(&axis))).normalize()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::fixtures::mesh;
+    use crate::model::normal::{NormalMode, DEFAULT_CREASE_ANGLE};
+
+    // A unit quad in the z = 0 plane, with the UVs given by its corners.
+    fn quad(uv: impl Fn(f32, f32) -> (f32, f32)) -> Model {
+        let corners = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]];
+        let mut model = mesh(&corners, &[&[0, 1, 2, 3]]);
+        model.uv = corners.iter().map(|[x, y, _]| uv(*x, *y).into()).collect();
+        model.set_normals(NormalMode::Angle, DEFAULT_CREASE_ANGLE);
+        model
+    }
+
+    fn assert_tangent(t: &Vec4d, expected: [f32; 4]) {
+        let actual = [t.d0, t.d1, t.d2, t.d3];
+        assert!(actual.iter().zip(expected).all(|(a, e)| (a - e).abs() < 1e-5), "{:?} != {:?}", actual, expected);
+    }
+
+    #[test]
+    fn axis_aligned_uvs_give_the_x_axis() {
+        for tangent in quad(|x, y| (x, y)).tangents() {
+            assert_tangent(&tangent, [1.0, 0.0, 0.0, 1.0]);
+        }
+    }
+
+    #[test]
+    fn mirrored_uvs_flip_the_handedness() {
+        for tangent in quad(|x, y| (1.0 - x, y)).tangents() {
+            assert_tangent(&tangent, [-1.0, 0.0, 0.0, -1.0]);
+        }
+    }
+
+    #[test]
+    fn rotated_uvs_turn_the_tangent() {
+        // u runs along y.
+        for tangent in quad(|x, y| (y, 1.0 - x)).tangents() {
+            assert_tangent(&tangent, [0.0, 1.0, 0.0, 1.0]);
+        }
+    }
+
+    #[test]
+    fn missing_uvs_give_a_perpendicular_tangent() {
+        let mut model = quad(|x, y| (x, y));
+        model.uv.clear();
+        for tangent in model.tangents() {
+            let t = Vec3d::new(tangent.d0, tangent.d1, tangent.d2);
+            assert!((t.length() - 1.0).abs() < 1e-5);
+            assert!(t.d2.abs() < 1e-5);
+        }
+    }
+}
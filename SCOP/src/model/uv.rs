@@ -0,0 +1,187 @@
+use std::f32::consts::PI;
+use std::str::FromStr;
+
+use super::Model;
+use crate::mat::{Vec2d, Vec3d};
+
+
+// `Faces` keeps the UVs given with the model, or the same corners on every
+// face when there are none. The others are computed from the positions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UvProjection {
+    Faces,
+    Planar,
+    Box,
+    Spherical,
+    Cylindrical,
+}
+
+impl FromStr for UvProjection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "faces" => Ok(UvProjection::Faces),
+            "planar" => Ok(UvProjection::Planar),
+            "box" => Ok(UvProjection::Box),
+            "spherical" => Ok(UvProjection::Spherical),
+            "cylindrical" => Ok(UvProjection::Cylindrical),
+            _ => Err(format!("error: unknown uv projection: {}", s)),
+        }
+    }
+}
+
+impl UvProjection {
+    pub fn next(&self) -> Self {
+        match self {
+            UvProjection::Faces => UvProjection::Planar,
+            UvProjection::Planar => UvProjection::Box,
+            UvProjection::Box => UvProjection::Spherical,
+            UvProjection::Spherical => UvProjection::Cylindrical,
+            UvProjection::Cylindrical => UvProjection::Faces,
+        }
+    }
+}
+
+impl Model {
+    pub fn project_uv(&self, projection: UvProjection) -> Vec<Vec2d> {
+        let (min, max) = self.bounding_box();
+        let center = min.add(&max).mul(0.5);
+        let extent = max.minus(&min);
+        let size = extent.d0.max(extent.d1).max(extent.d2).max(f32::EPSILON);
+        let mut uv = vec![Vec2d::new(0.0, 0.0); self.vertices.len()];
+        match projection {
+            UvProjection::Faces => {},
+            UvProjection::Planar => {
+                // Along the thinnest side of the bounding box.
+                let axis = if extent.d0 <= extent.d1 && extent.d0 <= extent.d2 {
+                    0
+                } else if extent.d1 <= extent.d2 {
+                    1
+                } else {
+                    2
+                };
+                for (i, v) in self.vertices.iter().enumerate() {
+                    uv[i] = planar(&v.minus(&min), axis, size);
+                }
+            },
+            UvProjection::Box => {
+                // Each face is projected along the main axis of its normal.
+                for corners in self.faces() {
+                    let normal = self.newell_normal(&corners);
+                    let axis = dominant_axis(&normal);
+                    for corner in corners {
+                        uv[corner] = planar(&self.vertices[corner].minus(&min), axis, size);
+                    }
+                }
+            },
+            UvProjection::Spherical => {
+                let up = Vec3d::new(0.0, 1.0, 0.0);
+                for corners in self.faces() {
+                    let face_uv: Vec<Vec2d> = corners.iter()
+                        .map(|c| {
+                            let d = self.vertices[*c].minus(&center);
+                            let (u, _) = around_axis(&d, &up);
+                            let length = d.length().max(f32::EPSILON);
+                            Vec2d::new(u, 1.0 - (d.d1 / length).clamp(-1.0, 1.0).acos() / PI)
+                        })
+                        .collect();
+                    for (corner, value) in corners.iter().zip(unwrap_seam(face_uv)) {
+                        uv[*corner] = value;
+                    }
+                }
+            },
+            UvProjection::Cylindrical => {
+                let axis = self.principal_axis(&center);
+                let (low, high) = self.vertices.iter()
+                    .map(|v| v.minus(&center).inner_product(&axis))
+                    .fold((f32::MAX, f32::MIN), |(lo, hi), h| (lo.min(h), hi.max(h)));
+                let height = (high - low).max(f32::EPSILON);
+                for corners in self.faces() {
+                    let face_uv: Vec<Vec2d> = corners.iter()
+                        .map(|c| {
+                            let d = self.vertices[*c].minus(&center);
+                            let (u, h) = around_axis(&d, &axis);
+                            Vec2d::new(u, (h - low) / height)
+                        })
+                        .collect();
+                    for (corner, value) in corners.iter().zip(unwrap_seam(face_uv)) {
+                        uv[*corner] = value;
+                    }
+                }
+            },
+        }
+        uv
+    }
+
+    // Direction of the largest spread of the vertices, by power iteration on
+    // the covariance matrix.
+    fn principal_axis(&self, center: &Vec3d) -> Vec3d {
+        let mut covariance = [[0.0f32; 3]; 3];
+        for v in &self.vertices {
+            let d = v.minus(center);
+            let d = [d.d0, d.d1, d.d2];
+            for i in 0..3 {
+                for j in 0..3 {
+                    covariance[i][j] += d[i] * d[j];
+                }
+            }
+        }
+        let mut axis = Vec3d::new(0.0, 1.0, 0.0);
+        for _ in 0..32 {
+            let next = Vec3d::new(
+                covariance[0][0] * axis.d0 + covariance[0][1] * axis.d1 + covariance[0][2] * axis.d2,
+                covariance[1][0] * axis.d0 + covariance[1][1] * axis.d1 + covariance[1][2] * axis.d2,
+                covariance[2][0] * axis.d0 + covariance[2][1] * axis.d1 + covariance[2][2] * axis.d2,
+            );
+            if next.length() == 0.0 {
+                break;
+            }
+            axis = next.normalize();
+        }
+        axis
+    }
+}
+
+fn planar(p: &Vec3d, axis: usize, size: f32) -> Vec2d {
+    match axis {
+        0 => Vec2d::new(p.d2 / size, p.d1 / size),
+        1 => Vec2d::new(p.d0 / size, p.d2 / size),
+        _ => Vec2d::new(p.d0 / size, p.d1 / size),
+    }
+}
+
+fn dominant_axis(v: &Vec3d) -> usize {
+    let (x, y, z) = (v.d0.abs(), v.d1.abs(), v.d2.abs());
+    if x >= y && x >= z {
+        0
+    } else if y >= z {
+        1
+    } else {
+        2
+    }
+}
+
+// Angle around `axis` in [0, 1) and height along it.
+fn around_axis(d: &Vec3d, axis: &Vec3d) -> (f32, f32) {
+    let helper = if axis.d0.abs() < 0.9 { Vec3d::new(1.0, 0.0, 0.0) } else { Vec3d::new(0.0, 0.0, 1.0) };
+    let side = axis.cross_product(&helper).normalize();
+    let front = side.cross_product(axis);
+    let angle = d.inner_product(&side).atan2(d.inner_product(&front));
+    (angle / (2.0 * PI) + 0.5, d.inner_product(axis))
+}
+
+// A face crossing the seam at u = 0 / 1 would stretch over the whole
+// texture, so its small u values are moved past 1.
+fn unwrap_seam(mut uv: Vec<Vec2d>) -> Vec<Vec2d> {
+    let min = uv.iter().map(|v| v.d0).fold(f32::MAX, f32::min);
+    let max = uv.iter().map(|v| v.d0).fold(f32::MIN, f32::max);
+    if max - min > 0.5 {
+        for v in uv.iter_mut() {
+            if v.d0 < 0.5 {
+                v.d0 += 1.0;
+            }
+        }
+    }
+    uv
+}
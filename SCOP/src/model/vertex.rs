@@ -0,0 +1,40 @@
+use render_gl_derive::VertexAttribPointers;
+
+use crate::mat::{Vec2d, Vec3d, Vec4d};
+
+
+// One element of the interleaved vertex buffer. The locations match the
+// inputs of triangle.vert.
+#[derive(VertexAttribPointers, Copy, Clone, Debug)]
+#[repr(C, packed)]
+pub struct Vertex {
+    #[location = 0]
+    pub position: Vec3d,
+    #[location = 1]
+    pub color: Vec3d,
+    #[location = 2]
+    pub uv: Vec2d,
+    #[location = 3]
+    pub normal: Vec3d,
+    #[location = 5]
+    pub tangent: Vec4d,
+}
+
+// The same attributes for every corner of the triangulated faces, with the
+// barycentric coordinates drawing the polygon edges.
+#[derive(VertexAttribPointers, Copy, Clone, Debug)]
+#[repr(C, packed)]
+pub struct WireVertex {
+    #[location = 0]
+    pub position: Vec3d,
+    #[location = 1]
+    pub color: Vec3d,
+    #[location = 2]
+    pub uv: Vec2d,
+    #[location = 3]
+    pub normal: Vec3d,
+    #[location = 4]
+    pub barycentric: Vec3d,
+    #[location = 5]
+    pub tangent: Vec4d,
+}
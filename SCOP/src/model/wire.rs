@@ -0,0 +1,34 @@
+use super::Model;
+use super::vertex::WireVertex;
+use crate::mat::Vec3d;
+
+
+impl Model {
+    // Each face is split into a fan from its first corner. The coordinate of
+    // a corner is raised by 1 when the edge in front of it is a diagonal of
+    // the fan, so only the edges of the polygon reach 0.
+    pub fn wire_vertices(&self) -> Vec<WireVertex> {
+        let corners = self.interleaved_vertices();
+        let mut vertices = Vec::with_capacity(self.vertices.len() * 3);
+        for face in self.faces() {
+            let n = face.len();
+            for i in 1..n.saturating_sub(1) {
+                let hidden = [0.0, if i + 1 < n - 1 { 1.0 } else { 0.0 }, if i > 1 { 1.0 } else { 0.0 }];
+                for (k, corner) in [face[0], face[i], face[i + 1]].iter().enumerate() {
+                    let v = corners[*corner];
+                    let mut barycentric = hidden;
+                    barycentric[k] += 1.0;
+                    vertices.push(WireVertex {
+                        position: v.position,
+                        color: v.color,
+                        uv: v.uv,
+                        normal: v.normal,
+                        barycentric: Vec3d::new(barycentric[0], barycentric[1], barycentric[2]),
+                        tangent: v.tangent,
+                    });
+                }
+            }
+        }
+        vertices
+    }
+}
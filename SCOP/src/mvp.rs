@@ -32,13 +32,15 @@ pub struct MVPMatrix {
 }
 
 impl MVP {
+    // The model is centered on `center` and scaled so a sphere of `radius`
+    // around it has a radius of 1.
     pub fn new(program_id: gl::types::GLuint,
-        scale_max_size: f32,
-        vertices: &Vec<Vec3d>,
+        center: &Vec3d,
+        radius: f32,
         window_size: (u32, u32)
     ) -> Result<Self, String> {
-        let center_matrix = Self::make_center_matrix(vertices);
-        let scale_matrix = Self::make_scale_matrix(scale_max_size);
+        let center_matrix = Self::make_translation_matrix(&center.mul(-1.0));
+        let scale_matrix = Self::make_scale_matrix(radius);
         let rotation_matrix = Matrix::make_identity_matrix();
         let translation_vec = Vec3d::new(0.0, 0.0, DEFAULT_TRANSLATION_Z);
         let translation_matrix = Self::make_translation_matrix(&translation_vec);
@@ -64,17 +66,8 @@ impl MVP {
         })
     }
 
-    fn make_center_matrix(vertices: &Vec<Vec3d>) -> Matrix {
-        let mut vec = Vec3d::new(0.0, 0.0, 0.0);
-        for v in vertices {
-            vec = vec.add(v);
-        }
-        vec = vec.mul(- 1.0 / vertices.len() as f32);
-        return Self::make_translation_matrix(&vec)
-    }
-
-    fn make_scale_matrix(scale_max_size: f32) -> Matrix {
-        Matrix::make_scale_matrix((1.0 / scale_max_size).min(MAX_SCALE))
+    fn make_scale_matrix(radius: f32) -> Matrix {
+        Matrix::make_scale_matrix((1.0 / radius).min(MAX_SCALE))
     }
 
     fn make_translation_matrix(translation_vec: &Vec3d) -> Matrix {
@@ -100,18 +93,21 @@ impl MVP {
         )
     }
 
-    pub fn update_translation_mouse(&mut self, x: f32, y: f32, diff_time: f32) {
-        self.translation_vec.d0 += x * TRANSLATION_SPEED * diff_time / 1000.0;
-        self.translation_vec.d1 -= y * TRANSLATION_SPEED * diff_time / 1000.0;
-        self.update_translation_matrix();
+    // The deltas of the mouse are in view space. They move the model, or
+    // the selected object of the scene.
+    pub fn mouse_translation(x: f32, y: f32, diff_time: f32) -> Vec3d {
+        Vec3d::new(
+            x * TRANSLATION_SPEED * diff_time / 1000.0,
+            -y * TRANSLATION_SPEED * diff_time / 1000.0,
+            0.0
+        )
     }
 
-    pub fn update_translation_wheel(&mut self, z: f32) {
-        self.translation_vec.d2 += z * TRANSLATION_WHEEL_SPEED;
-        self.update_translation_matrix();
+    pub fn wheel_translation(z: f32) -> Vec3d {
+        Vec3d::new(0.0, 0.0, z * TRANSLATION_WHEEL_SPEED)
     }
 
-    pub fn update_rotate_matrix(&mut self, x: i32, y: i32, diff_time: f32) {
+    pub fn mouse_rotation(x: i32, y: i32, diff_time: f32) -> Matrix {
         let direction_vec = if y == 0 {
             let v = if x > 0 {1} else {-1} as f32;
             Vec3d::new(0.0, v, 0.0)
@@ -121,26 +117,65 @@ impl MVP {
         } else {
             Vec3d::new(y as f32, x as f32, 0.0).normalize()
         };
-        let rotation_matrix =
-            Matrix::make_rotate_matrix(
-                &direction_vec,
-                std::f32::consts::PI * ROTATION_SPEED * diff_time / 1000.0
-            ).mul(&self.rotation_matrix.matrix)
-            .orthonormalization();
-        self.rotation_matrix.matrix_update(rotation_matrix);
+        Matrix::make_rotate_matrix(
+            &direction_vec,
+            std::f32::consts::PI * ROTATION_SPEED * diff_time / 1000.0
+        )
     }
 
-    pub fn update_z_rotate_matrix(&mut self, x: i32, diff_time: f32) {
+    pub fn z_rotation(x: i32, diff_time: f32) -> Matrix {
         let direction_vec = Vec3d::new(0.0, 0.0, 1.0);
-        let rotation_matrix =
-            Matrix::make_rotate_matrix(
-                &direction_vec,
-                x as f32 * std::f32::consts::PI * ROTATION_SPEED * diff_time / 1000.0
-            ).mul(&self.rotation_matrix.matrix)
+        Matrix::make_rotate_matrix(
+            &direction_vec,
+            x as f32 * std::f32::consts::PI * ROTATION_SPEED * diff_time / 1000.0
+        )
+    }
+
+    pub fn translate(&mut self, delta: &Vec3d) {
+        self.translation_vec = self.translation_vec.add(delta);
+        self.update_translation_matrix();
+    }
+
+    pub fn rotate(&mut self, rotation: &Matrix) {
+        let rotation_matrix = rotation
+            .mul(&self.rotation_matrix.matrix)
             .orthonormalization();
         self.rotation_matrix.matrix_update(rotation_matrix);
     }
 
+    pub fn rotation(&self) -> &Matrix {
+        self.rotation_matrix.matrix()
+    }
+
+    pub fn projection(&self) -> &Matrix {
+        self.projection_matrix.matrix()
+    }
+
+    // Radius on screen of a sphere of the world, in halves of the window
+    // height. The sphere is seen from its center.
+    pub fn screen_radius(&self, center: &Vec3d, radius: f32) -> f32 {
+        let radius = self.scale_matrix.matrix().transform([radius, 0.0, 0.0, 0.0])[0];
+        let view = self.model_view().transform([center.d0, center.d1, center.d2, 1.0]);
+        let center = self.projection().transform(view);
+        if center[3] <= 0.0 {
+            return 0.0
+        }
+        self.projection().transform([0.0, radius, 0.0, 0.0])[1] / center[3]
+    }
+
+    // From world space to view space, before the projection.
+    pub fn model_view(&self) -> Matrix {
+        self.translation_matrix.matrix()
+            .mul(self.rotation_matrix.matrix())
+            .mul(self.scale_matrix.matrix())
+            .mul(self.center_matrix.matrix())
+    }
+
+    // The whole transform, for the shaders without the separate matrices.
+    pub fn model_view_projection(&self) -> Matrix {
+        self.projection_matrix.matrix().mul(&self.model_view())
+    }
+
     pub fn set(&self) {
         self.center_matrix.set();
         self.scale_matrix.set();
@@ -157,7 +192,6 @@ impl MVPMatrix {
         Ok(Self { name, id, matrix })
     }
 
-    #[allow(dead_code)]
     pub fn matrix(&self) -> &Matrix {
         &self.matrix
     }
@@ -178,3 +212,34 @@ impl MVPMatrix {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::culling::{Winding, DEFAULT_FRONT_FACE};
+    use crate::model::Model;
+
+    // The face of the sample cube passing the depth test in the starting
+    // view must be front-facing with the default winding.
+    #[test]
+    fn sample_cube_near_face_is_front_facing() {
+        let mut model = Model::new();
+        model.set_cube_sample_uv();
+        let mvp = MVP::make_projection_matrix((900, 700))
+            .mul(&MVP::make_translation_matrix(&Vec3d::new(0.0, 0.0, DEFAULT_TRANSLATION_Z)));
+        let ndc = |v: &Vec3d| {
+            let clip = mvp.transform([v.d0, v.d1, v.d2, 1.0]);
+            [clip[0] / clip[3], clip[1] / clip[3], clip[2] / clip[3]]
+        };
+        let depth = |t: &[[f32; 3]; 3]| t[0][2] + t[1][2] + t[2][2];
+        let near = model.get_vertices()
+            .chunks(3)
+            .map(|t| [ndc(&t[0]), ndc(&t[1]), ndc(&t[2])])
+            .min_by(|a, b| depth(a).total_cmp(&depth(b)))
+            .unwrap();
+        let [a, b, c] = near;
+        let area = (b[0] - a[0]) * (c[1] - a[1]) - (c[0] - a[0]) * (b[1] - a[1]);
+        let winding = if area > 0.0 { Winding::CounterClockwise } else { Winding::Clockwise };
+        assert_eq!(winding, DEFAULT_FRONT_FACE);
+    }
+}
@@ -0,0 +1,85 @@
+use std::ffi::CString;
+
+use crate::img_loader::{ColorSpace, Image};
+use crate::resource;
+use crate::texture::Texture;
+use crate::texture::options::TextureOptions;
+
+
+// The model texture is on unit 0 and the text overlay on unit 1.
+const TEXTURE_UNIT: u32 = 2;
+
+
+// A tangent space normal map, shared by the models, perturbing the normals
+// they are lit with.
+pub struct NormalMap {
+    texture: Option<Texture>,
+    on: bool,
+    has_normal_map_id: i32,
+    sampler_id: i32,
+}
+
+impl NormalMap {
+    // The texels are directions, not colors: they are flagged linear so the
+    // mipmaps are not averaged as gamma encoded colors.
+    pub fn load(path: Option<&str>, model_path: Option<&str>) -> Result<Self, String> {
+        let texture = match path {
+            Some(path) => {
+                let resolved = resource::resolve(path, model_path)
+                    .ok_or_else(|| format!("error: normal map not found: {}", path))?;
+                let image = Image::new(&resolved.to_string_lossy())?;
+                Some(Texture::from_image(image.with_color_space(ColorSpace::Linear)))
+            },
+            None => None,
+        };
+        Ok(Self { on: texture.is_some(), texture, has_normal_map_id: 0, sampler_id: 0 })
+    }
+
+    pub fn set_program(&mut self, program_id: gl::types::GLuint) -> Result<(), String> {
+        let has_normal_map = CString::new("HasNormalMap").map_err(|_| "error: GetUniformLocation".to_string())?;
+        let sampler = CString::new("NormalMap").map_err(|_| "error: GetUniformLocation".to_string())?;
+        unsafe {
+            self.has_normal_map_id = gl::GetUniformLocation(program_id, has_normal_map.as_ptr());
+            self.sampler_id = gl::GetUniformLocation(program_id, sampler.as_ptr());
+        }
+        Ok(())
+    }
+
+    pub fn set_texture(&mut self, options: &TextureOptions) {
+        let Some(texture) = &mut self.texture else {
+            return;
+        };
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + TEXTURE_UNIT);
+        }
+        texture.set_texture(options);
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0);
+        }
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.texture.is_some()
+    }
+
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+
+    pub fn switch(&mut self) {
+        self.on = !self.on && self.is_loaded();
+    }
+
+    // The program must be in use.
+    pub fn set(&self) {
+        unsafe {
+            gl::Uniform1i(self.sampler_id, TEXTURE_UNIT as i32);
+            gl::Uniform1i(self.has_normal_map_id, self.on as i32);
+            if let Some(texture) = &self.texture {
+                gl::ActiveTexture(gl::TEXTURE0 + TEXTURE_UNIT);
+                texture.bind();
+                gl::ActiveTexture(gl::TEXTURE0);
+            }
+        }
+    }
+}
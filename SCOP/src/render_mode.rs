@@ -0,0 +1,107 @@
+use std::ffi::CString;
+
+
+const WIRE_COLOR: [f32; 3] = [0.05, 0.05, 0.05];
+const POINT_SIZE: f32 = 4.0;
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenderMode {
+    Solid,
+    Wireframe,
+    SolidWireframe,
+    Points,
+    HiddenLine,
+}
+
+// Wireframe and points show the triangles as uploaded; the overlays draw
+// the edges of the original polygons from barycentric coordinates.
+#[derive(Debug)]
+pub struct Rendering {
+    mode: RenderMode,
+    fill_color: [f32; 3],
+    wire_mode_id: i32,
+    wire_color_id: i32,
+    fill_color_id: i32,
+}
+
+impl RenderMode {
+    fn next(&self) -> Self {
+        match self {
+            RenderMode::Solid => RenderMode::Wireframe,
+            RenderMode::Wireframe => RenderMode::SolidWireframe,
+            RenderMode::SolidWireframe => RenderMode::Points,
+            RenderMode::Points => RenderMode::HiddenLine,
+            RenderMode::HiddenLine => RenderMode::Solid,
+        }
+    }
+
+    fn wire_mode(self) -> i32 {
+        match self {
+            RenderMode::SolidWireframe => 1,
+            RenderMode::HiddenLine => 2,
+            _ => 0,
+        }
+    }
+}
+
+impl Rendering {
+    // Hidden lines are drawn over faces filled with `fill_color`, the
+    // background color, so the faces only hide what is behind them.
+    pub fn new(fill_color: [f32; 3]) -> Self {
+        Self {
+            mode: RenderMode::Solid,
+            fill_color,
+            wire_mode_id: 0,
+            wire_color_id: 0,
+            fill_color_id: 0,
+        }
+    }
+
+    pub fn set_program(&mut self, program_id: gl::types::GLuint) -> Result<(), String> {
+        self.wire_mode_id = Self::get_uniform_location(program_id, "WireMode")?;
+        self.wire_color_id = Self::get_uniform_location(program_id, "WireColor")?;
+        self.fill_color_id = Self::get_uniform_location(program_id, "FillColor")?;
+        Ok(())
+    }
+
+    fn get_uniform_location(program_id: gl::types::GLuint, name: &str) -> Result<i32, String> {
+        let name = CString::new(name).map_err(|_| "error: GetUniformLocation".to_string())?;
+        Ok(unsafe { gl::GetUniformLocation(program_id, name.as_ptr()) })
+    }
+
+    pub fn mode(&self) -> RenderMode {
+        self.mode
+    }
+
+    pub fn switch(&mut self) {
+        self.mode = self.mode.next();
+    }
+
+    // The overlays need the unwelded triangles of `Model::draw_wire`.
+    pub fn uses_barycentric(&self) -> bool {
+        self.mode.wire_mode() != 0
+    }
+
+    pub fn set(&self) {
+        let polygon_mode = match self.mode {
+            RenderMode::Wireframe => gl::LINE,
+            RenderMode::Points => gl::POINT,
+            _ => gl::FILL,
+        };
+        unsafe {
+            gl::PolygonMode(gl::FRONT_AND_BACK, polygon_mode);
+            gl::PointSize(POINT_SIZE);
+            gl::Uniform1i(self.wire_mode_id, self.mode.wire_mode());
+            gl::Uniform3f(self.wire_color_id, WIRE_COLOR[0], WIRE_COLOR[1], WIRE_COLOR[2]);
+            gl::Uniform3f(self.fill_color_id, self.fill_color[0], self.fill_color[1], self.fill_color[2]);
+        }
+    }
+
+    // The other passes draw filled polygons.
+    pub fn unset(&self) {
+        unsafe {
+            gl::PolygonMode(gl::FRONT_AND_BACK, gl::FILL);
+        }
+    }
+}
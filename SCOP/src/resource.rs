@@ -0,0 +1,43 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+
+// Where the bundled assets can be, relative to the executable: next to it,
+// in the repository root (where make copies it) or in target/{debug,release}.
+const ASSET_DIRS: [&str; 3] = [".", "SCOP", "../.."];
+
+
+// Looks for `path` as given, next to the model file and next to the executable.
+pub fn resolve(path: &str, model_path: Option<&str>) -> Option<PathBuf> {
+    let path = Path::new(path);
+    if path.is_absolute() {
+        return if path.is_file() { Some(path.to_path_buf()) } else { None }
+    }
+    let mut candidates = vec![path.to_path_buf()];
+    if let Some(dir) = model_path.and_then(|p| Path::new(p).parent()) {
+        candidates.push(dir.join(path));
+    }
+    if let Some(dir) = executable_dir() {
+        candidates.push(dir.join(path));
+    }
+    candidates.into_iter().find(|p| p.is_file())
+}
+
+// Looks for a file shipped in SCOP/asserts.
+pub fn resolve_asset(path: &str) -> Option<PathBuf> {
+    let dir = executable_dir()?;
+    let asset = Path::new("asserts").join(path);
+    std::iter::once(asset.clone())
+        .chain(ASSET_DIRS.iter().map(|d| dir.join(d).join(&asset)))
+        .find(|p| p.is_file())
+}
+
+fn executable_dir() -> Option<PathBuf> {
+    env::current_exe().ok()?.parent().map(|p| p.to_path_buf())
+}
+
+pub fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string_lossy().into_owned())
+}
@@ -0,0 +1,320 @@
+use crate::bbox;
+use crate::mat::{Matrix, Vec3d};
+use crate::material::{Material, AlphaMode};
+use crate::model::Model;
+use crate::model::stats::MeshStats;
+use crate::mvp::{MVP, MVPMatrix};
+use crate::render_mode::Rendering;
+use crate::texture::manager::TextureManager;
+
+
+// The objects without a parent are put side by side along x, the children
+// next to their parent, in its frame where it has a radius of 1.
+const LAYOUT_SPACING: f32 = 2.5;
+const CHILD_OFFSET: f32 = 2.0;
+const CHILD_SPACING: f32 = 1.5;
+const CHILD_SCALE: f32 = 0.5;
+
+
+#[derive(Debug, Clone)]
+pub struct Transform {
+    pub translation: Vec3d,
+    pub rotation: Matrix,
+    pub scale: f32,
+}
+
+pub struct SceneObject {
+    pub name: String,
+    pub model: Model,
+    pub material: Material,
+    pub texture: usize,
+    pub transform: Transform,
+    // Of the mesh as loaded, before any simplification.
+    stats: Option<MeshStats>,
+    parent: Option<usize>,
+    // Centers the model on its bounding box and scales its bounding sphere
+    // to a radius of 1, as `MVP` does for the whole scene.
+    normalize: Matrix,
+}
+
+// With no object selected, the mouse moves the camera and the keys act on
+// all the objects.
+pub struct Scene {
+    objects: Vec<SceneObject>,
+    selected: Option<usize>,
+    program_id: gl::types::GLuint,
+    world: MVPMatrix,
+}
+
+impl Transform {
+    pub fn new(translation: Vec3d, scale: f32) -> Self {
+        Self { translation, rotation: Matrix::make_identity_matrix(), scale }
+    }
+
+    pub fn matrix(&self) -> Matrix {
+        Matrix::make_translation_matrix(self.translation.d0, self.translation.d1, self.translation.d2)
+            .mul(&self.rotation)
+            .mul(&Matrix::make_scale_matrix(self.scale))
+    }
+}
+
+impl SceneObject {
+    pub fn new(name: String, model: Model, stats: Option<MeshStats>, material: Material, texture: usize) -> Self {
+        let center = model.bounding_box_center();
+        let (_, radius) = model.bounding_sphere();
+        let normalize = Matrix::make_scale_matrix(1.0 / radius.max(f32::EPSILON))
+            .mul(&Matrix::make_translation_matrix(-center.d0, -center.d1, -center.d2));
+        Self {
+            name,
+            model,
+            material,
+            texture,
+            transform: Transform::new(Vec3d::new(0.0, 0.0, 0.0), 1.0),
+            stats,
+            parent: None,
+            normalize,
+        }
+    }
+
+    pub fn parent(&self) -> Option<usize> {
+        self.parent
+    }
+
+    pub fn set_texture(&mut self, index: usize, textures: &TextureManager) {
+        self.texture = index;
+        self.material.set_texture_alpha(textures.has_alpha(index));
+    }
+}
+
+impl Scene {
+    pub fn new(program_id: gl::types::GLuint) -> Result<Self, String> {
+        Ok(Self {
+            objects: Vec::new(),
+            selected: None,
+            program_id,
+            world: MVPMatrix::new(program_id, "World", Matrix::make_identity_matrix())?,
+        })
+    }
+
+    pub fn add(&mut self, mut object: SceneObject) -> Result<usize, String> {
+        object.model.set_texture(self.program_id)?;
+        object.material.set_program(self.program_id)?;
+        self.objects.push(object);
+        Ok(self.objects.len() - 1)
+    }
+
+    pub fn set_parent(&mut self, child: usize, parent: usize) -> Result<(), String> {
+        if child >= self.objects.len() || parent >= self.objects.len() {
+            return Err(format!("error: parent: no object {}", child.max(parent) + 1))
+        }
+        let mut ancestor = Some(parent);
+        while let Some(i) = ancestor {
+            if i == child {
+                return Err(format!("error: parent: {} would be its own ancestor", self.objects[child].name))
+            }
+            ancestor = self.objects[i].parent;
+        }
+        self.objects[child].parent = Some(parent);
+        Ok(())
+    }
+
+    pub fn layout(&mut self) {
+        let roots: Vec<usize> = (0..self.objects.len()).filter(|i| self.objects[*i].parent.is_none()).collect();
+        for (k, i) in roots.iter().enumerate() {
+            let x = (k as f32 - (roots.len() - 1) as f32 / 2.0) * LAYOUT_SPACING;
+            self.objects[*i].transform = Transform::new(Vec3d::new(x, 0.0, 0.0), 1.0);
+        }
+        for parent in 0..self.objects.len() {
+            let children: Vec<usize> = (0..self.objects.len()).filter(|i| self.objects[*i].parent == Some(parent)).collect();
+            for (k, i) in children.iter().enumerate() {
+                let x = CHILD_OFFSET + k as f32 * CHILD_SPACING;
+                self.objects[*i].transform = Transform::new(Vec3d::new(x, 0.0, 0.0), CHILD_SCALE);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn objects(&self) -> &[SceneObject] {
+        &self.objects
+    }
+
+    pub fn selected(&self) -> Option<&SceneObject> {
+        self.selected.map(|i| &self.objects[i])
+    }
+
+    // The camera comes after the last object.
+    pub fn select_next(&mut self) {
+        self.selected = match self.selected {
+            None if !self.objects.is_empty() => Some(0),
+            Some(i) if i + 1 < self.objects.len() => Some(i + 1),
+            _ => None,
+        };
+    }
+
+    pub fn selection_name(&self) -> &str {
+        self.selected().map_or("camera", |object| object.name.as_str())
+    }
+
+    // The selected object, or all of them when the camera is selected.
+    pub fn targets_mut(&mut self) -> impl Iterator<Item = &mut SceneObject> {
+        let selected = self.selected;
+        self.objects.iter_mut()
+            .enumerate()
+            .filter(move |(i, _)| selected.map_or(true, |s| s == *i))
+            .map(|(_, object)| object)
+    }
+
+    // From the model of the object to the world.
+    pub fn world(&self, index: usize) -> Matrix {
+        let object = &self.objects[index];
+        let local = object.transform.matrix().mul(&object.normalize);
+        match object.parent {
+            Some(parent) => self.world(parent).mul(&local),
+            None => local,
+        }
+    }
+
+    // The bounding sphere of an object, in the world.
+    fn world_sphere(&self, index: usize) -> (Vec3d, f32) {
+        let world = self.world(index);
+        let center = world.transform([0.0, 0.0, 0.0, 1.0]);
+        let axis = world.transform([1.0, 0.0, 0.0, 0.0]);
+        (
+            Vec3d::new(center[0], center[1], center[2]),
+            Vec3d::new(axis[0], axis[1], axis[2]).length(),
+        )
+    }
+
+    // A sphere around the bounding spheres of the objects, to frame them.
+    pub fn bounds(&self) -> (Vec3d, f32) {
+        let spheres: Vec<(Vec3d, f32)> = (0..self.objects.len()).map(|i| self.world_sphere(i)).collect();
+        if spheres.is_empty() {
+            return (Vec3d::new(0.0, 0.0, 0.0), 1.0)
+        }
+        let mut min = [f32::MAX; 3];
+        let mut max = [f32::MIN; 3];
+        for (center, radius) in &spheres {
+            let center = [center.d0, center.d1, center.d2];
+            for axis in 0..3 {
+                min[axis] = min[axis].min(center[axis] - radius);
+                max[axis] = max[axis].max(center[axis] + radius);
+            }
+        }
+        let center = Vec3d::new((min[0] + max[0]) / 2.0, (min[1] + max[1]) / 2.0, (min[2] + max[2]) / 2.0);
+        let radius = spheres.iter()
+            .map(|(c, r)| c.minus(&center).length() + r)
+            .fold(0.0, f32::max);
+        (center, radius)
+    }
+
+    // The frame the selected object moves in: its parent, seen from the
+    // camera.
+    fn selected_frame(&self, model_view: &Matrix) -> Option<(usize, Matrix)> {
+        let index = self.selected?;
+        let frame = match self.objects[index].parent {
+            Some(parent) => model_view.mul(&self.world(parent)),
+            None => model_view.clone(),
+        };
+        Some((index, frame))
+    }
+
+    // `delta` is in view space. Returns false when the camera is selected.
+    pub fn translate_selected(&mut self, delta: &Vec3d, model_view: &Matrix) -> bool {
+        let Some((index, frame)) = self.selected_frame(model_view) else {
+            return false
+        };
+        let Some(inverse) = frame.inverse() else {
+            return true
+        };
+        let delta = inverse.transform([delta.d0, delta.d1, delta.d2, 0.0]);
+        let transform = &mut self.objects[index].transform;
+        transform.translation = transform.translation.add(&Vec3d::new(delta[0], delta[1], delta[2]));
+        true
+    }
+
+    // `rotation` is in view space, around the origin of the object.
+    pub fn rotate_selected(&mut self, rotation: &Matrix, model_view: &Matrix) -> bool {
+        let Some((index, frame)) = self.selected_frame(model_view) else {
+            return false
+        };
+        let frame = frame.orthonormalization();
+        let Some(inverse) = frame.inverse() else {
+            return true
+        };
+        let transform = &mut self.objects[index].transform;
+        transform.rotation = inverse.mul(rotation).mul(&frame).mul(&transform.rotation).orthonormalization();
+        true
+    }
+
+    // The bounding boxes to draw, as transforms of the unit cube.
+    pub fn bounding_boxes(&self) -> Vec<Matrix> {
+        (0..self.objects.len())
+            .filter(|i| self.selected.map_or(true, |s| s == *i))
+            .map(|i| {
+                let (min, max) = self.objects[i].model.bounding_box();
+                self.world(i).mul(&bbox::box_matrix(&min, &max))
+            })
+            .collect()
+    }
+
+    pub fn stats_lines(&self) -> Vec<String> {
+        match self.selected() {
+            Some(object) => {
+                let mut lines = vec![object.name.clone()];
+                lines.extend(object.stats.iter().flat_map(|s| s.lines()));
+                lines
+            },
+            None if self.objects.len() == 1 => {
+                self.objects[0].stats.as_ref().map(|s| s.lines()).unwrap_or_default()
+            },
+            None => {
+                let mut lines = vec![format!("scene: {} objects", self.objects.len())];
+                for object in &self.objects {
+                    if let Some(stats) = &object.stats {
+                        lines.push(format!("{}: {} faces, {} triangles", object.name, stats.faces, stats.triangles));
+                    }
+                }
+                lines
+            },
+        }
+    }
+
+    pub fn update_lods(&mut self, mvp: &MVP) {
+        for i in 0..self.objects.len() {
+            let (center, radius) = self.world_sphere(i);
+            self.objects[i].model.update_lod(mvp.screen_radius(&center, radius));
+        }
+    }
+
+    pub fn update_texture_fade(&mut self, elapsed_ms: f32) {
+        for object in self.objects.iter_mut() {
+            object.model.update_texture_fade(elapsed_ms);
+        }
+    }
+
+    // The blended objects are drawn last, over the others.
+    pub fn draw(&mut self, textures: &mut TextureManager, rendering: &Rendering) -> Result<(), String> {
+        let mut order: Vec<usize> = (0..self.objects.len()).collect();
+        order.sort_by_key(|i| self.objects[*i].material.alpha_mode() == AlphaMode::Blend);
+        for i in order {
+            let world = self.world(i);
+            self.world.matrix_update(world);
+            self.world.set();
+            let object = &self.objects[i];
+            textures.select(object.texture);
+            object.material.set();
+            rendering.set();
+            if rendering.uses_barycentric() {
+                object.model.draw_wire()?;
+            } else {
+                object.model.draw()?;
+            }
+            rendering.unset();
+            object.material.unset();
+        }
+        Ok(())
+    }
+}
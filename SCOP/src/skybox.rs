@@ -0,0 +1,91 @@
+use std::ffi::CString;
+
+use crate::gl_object::{VertexArray, Buffer};
+use crate::mvp::{MVP, MVPMatrix};
+use crate::shader::Program;
+use crate::texture::cubemap::Cubemap;
+
+
+// Two triangles for each face of a cube around the camera.
+const CUBE: [[f32; 3]; 36] = [
+    [-1.0, -1.0, -1.0], [1.0, -1.0, -1.0], [1.0, 1.0, -1.0],
+    [1.0, 1.0, -1.0], [-1.0, 1.0, -1.0], [-1.0, -1.0, -1.0],
+    [-1.0, -1.0, 1.0], [1.0, 1.0, 1.0], [1.0, -1.0, 1.0],
+    [1.0, 1.0, 1.0], [-1.0, -1.0, 1.0], [-1.0, 1.0, 1.0],
+    [-1.0, -1.0, -1.0], [-1.0, 1.0, 1.0], [-1.0, -1.0, 1.0],
+    [-1.0, 1.0, 1.0], [-1.0, -1.0, -1.0], [-1.0, 1.0, -1.0],
+    [1.0, -1.0, -1.0], [1.0, -1.0, 1.0], [1.0, 1.0, 1.0],
+    [1.0, 1.0, 1.0], [1.0, 1.0, -1.0], [1.0, -1.0, -1.0],
+    [-1.0, -1.0, -1.0], [-1.0, -1.0, 1.0], [1.0, -1.0, 1.0],
+    [1.0, -1.0, 1.0], [1.0, -1.0, -1.0], [-1.0, -1.0, -1.0],
+    [-1.0, 1.0, -1.0], [1.0, 1.0, 1.0], [-1.0, 1.0, 1.0],
+    [1.0, 1.0, 1.0], [-1.0, 1.0, -1.0], [1.0, 1.0, -1.0],
+];
+
+
+// Drawn first with the depth test off, so it stays behind the model.
+pub struct Skybox {
+    program: Program,
+    vao: VertexArray,
+    _vbo: Buffer,
+    cubemap: Cubemap,
+    rotation: MVPMatrix,
+    projection: MVPMatrix,
+    is_hdr_id: i32,
+}
+
+impl Skybox {
+    pub fn new(mut cubemap: Cubemap, mvp: &MVP) -> Result<Self, String> {
+        let program = Program::from_shaders_source(
+            &CString::new(include_str!("../asserts/shaders/skybox.vert")).map_err(|_| "error: skybox vertex shader".to_string())?,
+            &CString::new(include_str!("../asserts/shaders/skybox.frag")).map_err(|_| "error: skybox fragment shader".to_string())?
+        )?;
+        let vao = VertexArray::new();
+        vao.bind();
+        let vbo = Buffer::array();
+        vbo.upload(&CUBE, gl::STATIC_DRAW);
+        unsafe {
+            gl::EnableVertexAttribArray(0);
+            gl::VertexAttribPointer(
+                0,
+                3,
+                gl::FLOAT,
+                gl::FALSE,
+                0,
+                std::ptr::null(),
+            );
+        }
+        cubemap.set_texture();
+        let name = CString::new("IsHdr").map_err(|_| "error: GetUniformLocation".to_string())?;
+        let is_hdr_id = unsafe { gl::GetUniformLocation(program.id(), name.as_ptr()) };
+        Ok(Self {
+            rotation: MVPMatrix::new(program.id(), "Rotation", mvp.rotation().clone())?,
+            projection: MVPMatrix::new(program.id(), "Projection", mvp.projection().clone())?,
+            program,
+            vao,
+            _vbo: vbo,
+            cubemap,
+            is_hdr_id,
+        })
+    }
+
+    pub fn draw(&mut self, mvp: &MVP) {
+        self.rotation.matrix_update(mvp.rotation().clone());
+        self.projection.matrix_update(mvp.projection().clone());
+        self.program.set_used();
+        self.rotation.set();
+        self.projection.set();
+        unsafe {
+            gl::Uniform1i(self.is_hdr_id, self.cubemap.is_hdr() as i32);
+        }
+        self.vao.bind();
+        self.cubemap.bind();
+        unsafe {
+            gl::Disable(gl::DEPTH_TEST);
+            gl::DepthMask(gl::FALSE);
+            gl::DrawArrays(gl::TRIANGLES, 0, CUBE.len() as i32);
+            gl::DepthMask(gl::TRUE);
+            gl::Enable(gl::DEPTH_TEST);
+        }
+    }
+}
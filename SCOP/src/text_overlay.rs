@@ -0,0 +1,161 @@
+use std::ffi::CString;
+
+use crate::font;
+use crate::gl_object::{VertexArray, Buffer};
+use crate::img_loader::{Image, PixelFormat};
+use crate::img_loader::mipmap::MipFilter;
+use crate::shader::Program;
+use crate::texture::Texture;
+use crate::texture::options::{TextureOptions, WrapMode, MinFilter, Filter};
+
+
+const QUAD: [[f32; 2]; 4] = [[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [1.0, 1.0]];
+const MARGIN: usize = 4;
+const LINE_SPACING: usize = 3;
+// Window pixels per panel pixel, and distance to the window corner.
+const PIXEL_SCALE: usize = 2;
+const WINDOW_MARGIN: f32 = 8.0;
+const BACKGROUND: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+const TEXT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+// The model texture stays bound to unit 0.
+const TEXTURE_UNIT: u32 = 1;
+
+
+// Lines of text on a panel in the upper left corner of the window, drawn
+// over the scene with a pixel-sized texture.
+pub struct TextOverlay {
+    program: Program,
+    vao: VertexArray,
+    _vbo: Buffer,
+    texture: Texture,
+    size: (usize, usize),
+    rect_id: i32,
+    panel_id: i32,
+    on: bool,
+}
+
+impl TextOverlay {
+    pub fn new(lines: &[String]) -> Result<Self, String> {
+        let program = Program::from_shaders_source(
+            &CString::new(include_str!("../asserts/shaders/text.vert")).map_err(|_| "error: text vertex shader".to_string())?,
+            &CString::new(include_str!("../asserts/shaders/text.frag")).map_err(|_| "error: text fragment shader".to_string())?
+        )?;
+        let (texture, size) = Self::panel(lines);
+
+        let vao = VertexArray::new();
+        vao.bind();
+        let vbo = Buffer::array();
+        vbo.upload(&QUAD, gl::STATIC_DRAW);
+        unsafe {
+            gl::EnableVertexAttribArray(0);
+            gl::VertexAttribPointer(
+                0,
+                2,
+                gl::FLOAT,
+                gl::FALSE,
+                0,
+                std::ptr::null(),
+            );
+        }
+        let rect_name = CString::new("Rect").map_err(|_| "error: GetUniformLocation".to_string())?;
+        let panel_name = CString::new("Panel").map_err(|_| "error: GetUniformLocation".to_string())?;
+        let (rect_id, panel_id) = unsafe {
+            (
+                gl::GetUniformLocation(program.id(), rect_name.as_ptr()),
+                gl::GetUniformLocation(program.id(), panel_name.as_ptr()),
+            )
+        };
+        Ok(Self {
+            program,
+            vao,
+            _vbo: vbo,
+            texture,
+            size,
+            rect_id,
+            panel_id,
+            on: false,
+        })
+    }
+
+    fn panel(lines: &[String]) -> (Texture, (usize, usize)) {
+        let line_height = font::GLYPH_HEIGHT + LINE_SPACING;
+        let width = lines.iter().map(|l| font::text_width(l, 1)).max().unwrap_or(0) + 2 * MARGIN;
+        let height = lines.len() * line_height + 2 * MARGIN - LINE_SPACING;
+        let mut image = Image::blank(width, height, PixelFormat::RGBA8);
+        for y in 0..height {
+            for x in 0..width {
+                image.set_pixel(x, y, BACKGROUND);
+            }
+        }
+        // Image rows go up, the first line is at the top.
+        for (i, line) in lines.iter().enumerate() {
+            let y = height - MARGIN - font::GLYPH_HEIGHT - i * line_height;
+            font::draw_text(&mut image, MARGIN, y, line, 1, TEXT_COLOR);
+        }
+        let mut texture = Texture::from_image(image);
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + TEXTURE_UNIT);
+        }
+        texture.set_texture(&TextureOptions {
+            wrap: WrapMode::ClampToEdge,
+            min_filter: MinFilter::Nearest,
+            mag_filter: Filter::Nearest,
+            anisotropy: 1.0,
+            mip_filter: MipFilter::Box,
+        });
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0);
+        }
+        (texture, (width, height))
+    }
+
+    pub fn set_lines(&mut self, lines: &[String]) {
+        (self.texture, self.size) = Self::panel(lines);
+    }
+
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+
+    pub fn switch(&mut self) {
+        self.on = !self.on;
+    }
+
+    pub fn draw(&self) {
+        if !self.on {
+            return;
+        }
+        let mut viewport = [0; 4];
+        unsafe {
+            gl::GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr());
+        }
+        let (window_width, window_height) = (viewport[2].max(1) as f32, viewport[3].max(1) as f32);
+        let width = (self.size.0 * PIXEL_SCALE) as f32;
+        let height = (self.size.1 * PIXEL_SCALE) as f32;
+        let left = -1.0 + 2.0 * WINDOW_MARGIN / window_width;
+        let top = 1.0 - 2.0 * WINDOW_MARGIN / window_height;
+        self.program.set_used();
+        unsafe {
+            gl::Uniform4f(
+                self.rect_id,
+                left,
+                top - 2.0 * height / window_height,
+                left + 2.0 * width / window_width,
+                top,
+            );
+            gl::Uniform1i(self.panel_id, TEXTURE_UNIT as i32);
+            gl::ActiveTexture(gl::TEXTURE0 + TEXTURE_UNIT);
+        }
+        self.texture.bind();
+        self.vao.bind();
+        unsafe {
+            gl::Disable(gl::DEPTH_TEST);
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+            gl::DrawArrays(gl::TRIANGLE_STRIP, 0, QUAD.len() as i32);
+            gl::Disable(gl::BLEND);
+            gl::Enable(gl::DEPTH_TEST);
+            gl::ActiveTexture(gl::TEXTURE0);
+        }
+    }
+}
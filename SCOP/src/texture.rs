@@ -1,45 +1,77 @@
 pub mod sample;
+pub mod options;
+pub mod noise;
+pub mod procedural;
+pub mod manager;
+pub mod cubemap;
 
 use crate::img_loader::Image;
+use crate::gl_object::TextureObject;
+use options::{TextureOptions, WrapMode};
 
 
 pub struct Texture {
-    id: gl::types::GLuint,
-    width: usize,
-    height: usize,
-    data: Vec<f32>,
+    object: Option<TextureObject>,
+    image: Image,
 }
 
 impl Texture {
+    #[allow(dead_code)]
     pub fn from_bmp_file(path: &str) -> Result<Self, String> {
         let img = Image::new(path)?;
-        let width = img.get_width();
-        let height = img.get_height();
-        let data = img.get_data().clone();
-        Ok(Self { id: 0, width, height, data })
+        Ok(Self::from_image(img))
     }
 
-    pub fn set_texture(&mut self) {
+    pub fn from_image(image: Image) -> Self {
+        Self { object: None, image }
+    }
+
+    pub fn has_alpha(&self) -> bool {
+        self.image.has_alpha()
+    }
+
+    pub fn set_texture(&mut self, options: &TextureOptions) {
+        let format = self.image.get_format();
+        let swizzle = format.gl_swizzle();
+        let mip_levels = if options.min_filter.uses_mipmap() {
+            self.image.mip_chain(options.mip_filter, options.wrap != WrapMode::ClampToEdge)
+        } else {
+            Vec::new()
+        };
+        // Setting the texture again replaces and deletes the previous object.
+        let object = TextureObject::new(gl::TEXTURE_2D);
+        object.bind();
+        self.object = Some(object);
         unsafe {
-            gl::GenTextures(1, &mut self.id);
-            gl::BindBuffer(gl::TEXTURE_2D, self.id);
-
-            gl::TexImage2D(
-                gl::TEXTURE_2D,
-                0,
-                gl::RGB as i32,
-                self.width as i32,
-                self.height as i32,
-                0,
-                gl::RGB,
-                gl::FLOAT,
-                self.data.as_ptr() as *const _,
-            );
-
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as i32);
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as i32);
+            // Rows of RGB8 or R8 images are not always 4 byte aligned.
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            for (level, image) in std::iter::once(&self.image).chain(mip_levels.iter()).enumerate() {
+                gl::TexImage2D(
+                    gl::TEXTURE_2D,
+                    level as i32,
+                    format.gl_internal_format() as i32,
+                    image.get_width() as i32,
+                    image.get_height() as i32,
+                    0,
+                    format.gl_format(),
+                    format.gl_type(),
+                    image.get_ptr(),
+                );
+            }
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_BASE_LEVEL, 0);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAX_LEVEL, mip_levels.len() as i32);
+
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_SWIZZLE_R, swizzle[0] as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_SWIZZLE_G, swizzle[1] as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_SWIZZLE_B, swizzle[2] as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_SWIZZLE_A, swizzle[3] as i32);
         }
+        options.apply(gl::TEXTURE_2D);
     }
-}
\ No newline at end of file
+
+    pub fn bind(&self) {
+        if let Some(object) = &self.object {
+            object.bind();
+        }
+    }
+}
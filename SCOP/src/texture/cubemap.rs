@@ -0,0 +1,187 @@
+use std::f32::consts::PI;
+
+use crate::img_loader::{ColorSpace, Image};
+use crate::img_loader::ops::srgb_to_linear;
+use crate::gl_object::TextureObject;
+use crate::resource;
+
+
+// Faces in the GL order: +X, -X, +Y, -Y, +Z, -Z.
+const FACE_TARGETS: [gl::types::GLenum; 6] = [
+    gl::TEXTURE_CUBE_MAP_POSITIVE_X,
+    gl::TEXTURE_CUBE_MAP_NEGATIVE_X,
+    gl::TEXTURE_CUBE_MAP_POSITIVE_Y,
+    gl::TEXTURE_CUBE_MAP_NEGATIVE_Y,
+    gl::TEXTURE_CUBE_MAP_POSITIVE_Z,
+    gl::TEXTURE_CUBE_MAP_NEGATIVE_Z,
+];
+
+// Cell (column, row from the top) of each face in a cross layout.
+const HORIZONTAL_CROSS: [(usize, usize); 6] = [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (3, 1)];
+const VERTICAL_CROSS: [(usize, usize); 6] = [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (1, 3)];
+
+
+pub struct Cubemap {
+    object: Option<TextureObject>,
+    faces: Vec<Image>,
+}
+
+impl Cubemap {
+    // `spec` is one cross layout or equirectangular (2:1) image, or six
+    // comma separated face images.
+    pub fn load(spec: &str, model_path: Option<&str>) -> Result<Self, String> {
+        let images = spec.split(',')
+            .map(|path| {
+                let resolved = resource::resolve(path, model_path)
+                    .ok_or_else(|| format!("error: cubemap image not found: {}", path))?;
+                Image::new(&resolved.to_string_lossy())
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        match images.len() {
+            1 if images[0].get_width() == images[0].get_height() * 2 => {
+                Self::from_equirectangular(&images[0], images[0].get_width() / 4)
+            },
+            1 => Self::from_cross(&images[0]),
+            6 => Self::from_faces(images),
+            _ => Err(format!("error: cubemap needs 1 cross image or 6 faces: {}", spec)),
+        }
+    }
+
+    // Faces are given as they look from inside the cube, in the GL order.
+    pub fn from_faces(faces: Vec<Image>) -> Result<Self, String> {
+        let size = faces[0].get_width();
+        let format = faces[0].get_format();
+        if faces.iter().any(|f| f.get_width() != size || f.get_height() != size || f.get_format() != format) {
+            return Err("error: cubemap faces must be squares of the same size and format".to_string())
+        }
+        // GL reads cubemap faces from the top row, images start at the bottom.
+        let faces = faces.iter().map(|f| f.flip_vertical()).collect();
+        Ok(Self { object: None, faces })
+    }
+
+    // Horizontal (4x3) or vertical (3x4) cross. The -Z face of a vertical
+    // cross is stored upside down.
+    pub fn from_cross(image: &Image) -> Result<Self, String> {
+        let (width, height) = (image.get_width(), image.get_height());
+        let (layout, rows, size) = if width * 3 == height * 4 {
+            (HORIZONTAL_CROSS, 3, width / 4)
+        } else if width * 4 == height * 3 {
+            (VERTICAL_CROSS, 4, width / 3)
+        } else {
+            return Err(format!("error: cubemap cross must be 4:3 or 3:4: {}x{}", width, height))
+        };
+        let mut faces = Vec::with_capacity(6);
+        for (i, (column, row)) in layout.iter().enumerate() {
+            let face = image.crop(column * size, (rows - 1 - row) * size, size, size)?;
+            faces.push(if rows == 4 && i == 5 {
+                face.flip_vertical().flip_horizontal()
+            } else {
+                face
+            });
+        }
+        Self::from_faces(faces)
+    }
+
+    // Samples a latitude / longitude panorama whose center looks at +Z.
+    pub fn from_equirectangular(image: &Image, size: usize) -> Result<Self, String> {
+        let (width, height) = (image.get_width() as f32, image.get_height() as f32);
+        let mut faces = Vec::with_capacity(6);
+        for face in 0..6 {
+            let mut img = Image::blank(size, size, image.get_format());
+            for y in 0..size {
+                for x in 0..size {
+                    let [dx, dy, dz] = Self::direction(face, x, y, size);
+                    let length = (dx * dx + dy * dy + dz * dz).sqrt();
+                    let u = 0.5 + dx.atan2(dz) / (2.0 * PI);
+                    let v = (dy / length).clamp(-1.0, 1.0).acos() / PI;
+                    // Image rows start at the bottom, v starts at the top.
+                    img.set_pixel(x, y, image.sample_bilinear(u * width - 0.5, (1.0 - v) * height - 0.5));
+                }
+            }
+            faces.push(img);
+        }
+        Self::from_faces(faces)
+    }
+
+    // Direction through the texel (x, y) of a face image as seen from inside,
+    // with y going up, following the GL face orientations.
+    fn direction(face: usize, x: usize, y: usize, size: usize) -> [f32; 3] {
+        let s = 2.0 * (x as f32 + 0.5) / size as f32 - 1.0;
+        let t = 1.0 - 2.0 * (y as f32 + 0.5) / size as f32;
+        match face {
+            0 => [1.0, -t, -s],
+            1 => [-1.0, -t, s],
+            2 => [s, 1.0, t],
+            3 => [s, -1.0, -t],
+            4 => [s, -t, 1.0],
+            _ => [-s, -t, -1.0],
+        }
+    }
+
+    pub fn is_hdr(&self) -> bool {
+        self.faces[0].get_format().is_float()
+    }
+
+    // Mean linear radiance over all directions, each texel weighted by its solid angle.
+    pub fn average_radiance(&self) -> [f32; 3] {
+        let gamma = self.faces[0].color_space() == ColorSpace::Srgb;
+        let mut sum = [0.0; 3];
+        let mut weight_sum = 0.0;
+        for (face, image) in self.faces.iter().enumerate() {
+            let size = image.get_width();
+            for y in 0..size {
+                for x in 0..size {
+                    let [dx, dy, dz] = Self::direction(face, x, y, size);
+                    let weight = (dx * dx + dy * dy + dz * dz).powf(-1.5);
+                    let p = image.get_pixel(x, y);
+                    for c in 0..3 {
+                        sum[c] += weight * if gamma { srgb_to_linear(p[c]) } else { p[c] };
+                    }
+                    weight_sum += weight;
+                }
+            }
+        }
+        sum.map(|v| v / weight_sum)
+    }
+
+    pub fn set_texture(&mut self) {
+        let format = self.faces[0].get_format();
+        let swizzle = format.gl_swizzle();
+        let object = TextureObject::new(gl::TEXTURE_CUBE_MAP);
+        object.bind();
+        unsafe {
+            gl::Enable(gl::TEXTURE_CUBE_MAP_SEAMLESS);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            for (target, face) in FACE_TARGETS.iter().zip(self.faces.iter()) {
+                gl::TexImage2D(
+                    *target,
+                    0,
+                    format.gl_internal_format() as i32,
+                    face.get_width() as i32,
+                    face.get_height() as i32,
+                    0,
+                    format.gl_format(),
+                    format.gl_type(),
+                    face.get_ptr(),
+                );
+            }
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_SWIZZLE_R, swizzle[0] as i32);
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_SWIZZLE_G, swizzle[1] as i32);
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_SWIZZLE_B, swizzle[2] as i32);
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_SWIZZLE_A, swizzle[3] as i32);
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_MAX_LEVEL, 0);
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_WRAP_R, gl::CLAMP_TO_EDGE as i32);
+        }
+        self.object = Some(object);
+    }
+
+    pub fn bind(&self) {
+        if let Some(object) = &self.object {
+            object.bind();
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use std::path::Path;
+
+use sdl2::keyboard::Keycode;
+
+use super::Texture;
+use super::options::TextureOptions;
+use super::procedural::{Procedural, ProceduralKind};
+use crate::args::Args;
+use crate::img_loader::Image;
+use crate::resource;
+
+
+const DEFAULT_TEXTURE: &str = "textures/sweets_shiroi_taiyaki_white.bmp";
+const FUNCTION_KEYS: [Keycode; 12] = [
+    Keycode::F1, Keycode::F2, Keycode::F3, Keycode::F4,
+    Keycode::F5, Keycode::F6, Keycode::F7, Keycode::F8,
+    Keycode::F9, Keycode::F10, Keycode::F11, Keycode::F12,
+];
+
+
+// The built-in procedural textures are only generated the first time they
+// are selected.
+enum Slot {
+    Loaded(Texture),
+    Pending(Procedural),
+}
+
+// Loaded textures come first, so F1 is the texture given on the command line,
+// followed by the procedural textures.
+pub struct TextureManager {
+    textures: Vec<(String, Slot)>,
+    options: TextureOptions,
+    active: usize,
+}
+
+impl TextureManager {
+    pub fn load(args: &Args) -> Result<Self, String> {
+        let mut textures: Vec<(String, Slot)> = Self::load_textures(args)?
+            .into_iter()
+            .map(|(name, texture)| (name, Slot::Loaded(texture)))
+            .collect();
+        for procedural in Procedural::all() {
+            textures.push((procedural.name().to_string(), Slot::Pending(procedural)));
+        }
+        Ok(Self { textures, options: args.texture_options.clone(), active: 0 })
+    }
+
+    fn load_textures(args: &Args) -> Result<Vec<(String, Texture)>, String> {
+        let mut textures = Vec::new();
+        for path in &args.texture_paths {
+            let image = match resource::resolve(path, args.model_path()) {
+                Some(resolved) => Image::new(&resolved.to_string_lossy()),
+                None => Err(format!("error: texture not found: {}", path)),
+            };
+            match image {
+                Ok(image) => textures.push((
+                    resource::file_stem(Path::new(path)),
+                    Texture::from_image(image.apply_ops(&args.image_ops)?)
+                )),
+                Err(e) => println!("{}", e),
+            }
+        }
+        if let Some(procedural) = &args.procedural {
+            textures.push((
+                procedural.name().to_string(),
+                Texture::from_image(procedural.generate().apply_ops(&args.image_ops)?)
+            ));
+        }
+        if args.texture_paths.is_empty() && textures.is_empty() {
+            if args.model_path().is_none() {
+                textures.push(("red_gradation".to_string(), Texture::red_gradation()));
+            } else if let Some(path) = resource::resolve_asset(DEFAULT_TEXTURE) {
+                match Image::new(&path.to_string_lossy()) {
+                    Ok(image) => textures.push((
+                        resource::file_stem(&path),
+                        Texture::from_image(image.apply_ops(&args.image_ops)?)
+                    )),
+                    Err(e) => println!("{}", e),
+                }
+            }
+        }
+        if textures.is_empty() {
+            println!("no texture found: using the built-in grid texture");
+            let procedural = Procedural::new(ProceduralKind::UvGrid);
+            textures.push((procedural.name().to_string(), Texture::from_image(procedural.generate())));
+        }
+        Ok(textures)
+    }
+
+    pub fn set_textures(&mut self, options: &TextureOptions) {
+        self.options = options.clone();
+        for (_, slot) in self.textures.iter_mut() {
+            if let Slot::Loaded(texture) = slot {
+                texture.set_texture(options);
+            }
+        }
+        self.bind();
+    }
+
+    pub fn function_key_index(keycode: Keycode) -> Option<usize> {
+        FUNCTION_KEYS.iter().position(|k| *k == keycode)
+    }
+
+    pub fn len(&self) -> usize {
+        self.textures.len()
+    }
+
+    // The procedural textures are opaque.
+    pub fn has_alpha(&self, index: usize) -> bool {
+        match &self.textures[index].1 {
+            Slot::Loaded(texture) => texture.has_alpha(),
+            Slot::Pending(_) => false,
+        }
+    }
+
+    pub fn name(&self, index: usize) -> &str {
+        &self.textures[index].0
+    }
+
+    // Binds the texture of an object before it is drawn.
+    pub fn select(&mut self, index: usize) -> bool {
+        if index >= self.textures.len() {
+            return false
+        }
+        if let Slot::Pending(procedural) = &self.textures[index].1 {
+            let mut texture = Texture::from_image(procedural.generate());
+            texture.set_texture(&self.options);
+            self.textures[index].1 = Slot::Loaded(texture);
+        }
+        self.active = index;
+        self.bind();
+        true
+    }
+
+    pub fn next_index(&self, index: usize) -> usize {
+        (index + 1) % self.textures.len()
+    }
+
+    pub fn previous_index(&self, index: usize) -> usize {
+        (index + self.textures.len() - 1) % self.textures.len()
+    }
+
+    pub fn bind(&self) {
+        if let Slot::Loaded(texture) = &self.textures[self.active].1 {
+            texture.bind();
+        }
+    }
+}
+
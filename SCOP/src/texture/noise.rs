@@ -0,0 +1,181 @@
+// Gradient and cellular noise used by the procedural textures.
+// Perlin and Worley noise tile with the given integer period so the
+// generated textures can be used with REPEAT wrapping.
+
+pub struct Noise {
+    perm: [usize; 512],
+    seed: u32,
+}
+
+pub struct Random {
+    state: u32,
+}
+
+impl Random {
+    pub fn new(seed: u32) -> Self {
+        Self { state: seed.wrapping_mul(0x9E37_79B9) | 1 }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        // xorshift32
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        x
+    }
+}
+
+pub fn hash2(x: i32, y: i32, seed: u32) -> u32 {
+    let mut h = seed ^ (x as u32).wrapping_mul(0x27d4_eb2d) ^ (y as u32).wrapping_mul(0x1656_67b1);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x85eb_ca6b);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xc2b2_ae35);
+    h ^ (h >> 16)
+}
+
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+const GRADIENTS: [(f32, f32); 8] = [
+    (1.0, 0.0), (-1.0, 0.0), (0.0, 1.0), (0.0, -1.0),
+    (0.70710677, 0.70710677), (-0.70710677, 0.70710677),
+    (0.70710677, -0.70710677), (-0.70710677, -0.70710677),
+];
+
+impl Noise {
+    pub fn new(seed: u32) -> Self {
+        let mut random = Random::new(seed);
+        let mut table: Vec<usize> = (0..256).collect();
+        for i in (1..256).rev() {
+            let j = random.next_u32() as usize % (i + 1);
+            table.swap(i, j);
+        }
+        let mut perm = [0; 512];
+        for i in 0..512 {
+            perm[i] = table[i % 256];
+        }
+        Self { perm, seed }
+    }
+
+    fn gradient(&self, x: i32, y: i32) -> (f32, f32) {
+        let index = self.perm[(self.perm[(x & 255) as usize] + (y & 255) as usize) & 511];
+        GRADIENTS[index & 7]
+    }
+
+    // Returns a value in about [-1, 1].
+    pub fn perlin(&self, x: f32, y: f32, period: i32) -> f32 {
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let (fx, fy) = (x - x0, y - y0);
+        let (ix, iy) = (x0 as i32, y0 as i32);
+        let wrap = |v: i32| if period > 0 { v.rem_euclid(period) } else { v };
+        let dot = |gx: i32, gy: i32, dx: f32, dy: f32| {
+            let g = self.gradient(wrap(gx), wrap(gy));
+            g.0 * dx + g.1 * dy
+        };
+        let n00 = dot(ix, iy, fx, fy);
+        let n10 = dot(ix + 1, iy, fx - 1.0, fy);
+        let n01 = dot(ix, iy + 1, fx, fy - 1.0);
+        let n11 = dot(ix + 1, iy + 1, fx - 1.0, fy - 1.0);
+        let u = fade(fx);
+        let v = fade(fy);
+        lerp(lerp(n00, n10, u), lerp(n01, n11, u), v) * std::f32::consts::SQRT_2
+    }
+
+    // 2D simplex noise, in about [-1, 1]. It does not tile.
+    pub fn simplex(&self, x: f32, y: f32) -> f32 {
+        const F2: f32 = 0.36602542;
+        const G2: f32 = 0.21132487;
+        let s = (x + y) * F2;
+        let i = (x + s).floor();
+        let j = (y + s).floor();
+        let t = (i + j) * G2;
+        let x0 = x - (i - t);
+        let y0 = y - (j - t);
+        let (i1, j1) = if x0 > y0 { (1, 0) } else { (0, 1) };
+        let x1 = x0 - i1 as f32 + G2;
+        let y1 = y0 - j1 as f32 + G2;
+        let x2 = x0 - 1.0 + 2.0 * G2;
+        let y2 = y0 - 1.0 + 2.0 * G2;
+        let (i, j) = (i as i32, j as i32);
+        let corner = |gx: i32, gy: i32, dx: f32, dy: f32| {
+            let t = 0.5 - dx * dx - dy * dy;
+            if t < 0.0 {
+                0.0
+            } else {
+                let g = self.gradient(gx, gy);
+                t * t * t * t * (g.0 * dx + g.1 * dy)
+            }
+        };
+        70.0 * (corner(i, j, x0, y0)
+            + corner(i + i1, j + j1, x1, y1)
+            + corner(i + 1, j + 1, x2, y2))
+    }
+
+    // Fractal sum of Perlin noise; the period doubles with the frequency, and
+    // the octaves whose period does not fit in an i32 do not tile.
+    pub fn fbm(&self, x: f32, y: f32, octaves: usize, period: i32) -> f32 {
+        let mut sum = 0.0;
+        let mut amplitude = 0.5;
+        let mut frequency = 1.0;
+        let mut norm = 0.0;
+        for i in 0..octaves {
+            let octave_period = 1i32.checked_shl(i as u32)
+                .and_then(|scale| period.checked_mul(scale))
+                .unwrap_or(0);
+            sum += amplitude * self.perlin(x * frequency, y * frequency, octave_period);
+            norm += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+        sum / norm
+    }
+
+    pub fn simplex_fbm(&self, x: f32, y: f32, octaves: usize) -> f32 {
+        let mut sum = 0.0;
+        let mut amplitude = 0.5;
+        let mut frequency = 1.0;
+        let mut norm = 0.0;
+        for _ in 0..octaves {
+            sum += amplitude * self.simplex(x * frequency, y * frequency);
+            norm += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+        sum / norm
+    }
+
+    // Distance to the nearest feature point (F1) and second nearest (F2),
+    // with one point per cell of a `cells` x `cells` grid over [0, cells).
+    pub fn worley(&self, x: f32, y: f32, cells: i32) -> (f32, f32) {
+        let cx = x.floor() as i32;
+        let cy = y.floor() as i32;
+        let mut f1 = f32::MAX;
+        let mut f2 = f32::MAX;
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let gx = cx + dx;
+                let gy = cy + dy;
+                let h = hash2(gx.rem_euclid(cells), gy.rem_euclid(cells), self.seed);
+                let px = gx as f32 + (h & 0xffff) as f32 / 65536.0;
+                let py = gy as f32 + (h >> 16) as f32 / 65536.0;
+                let d = ((px - x) * (px - x) + (py - y) * (py - y)).sqrt();
+                if d < f1 {
+                    f2 = f1;
+                    f1 = d;
+                } else if d < f2 {
+                    f2 = d;
+                }
+            }
+        }
+        (f1, f2)
+    }
+}
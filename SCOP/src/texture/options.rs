@@ -0,0 +1,147 @@
+use std::str::FromStr;
+
+use crate::img_loader::mipmap::MipFilter;
+
+
+const TEXTURE_MAX_ANISOTROPY_EXT: gl::types::GLenum = 0x84FE;
+const MAX_TEXTURE_MAX_ANISOTROPY_EXT: gl::types::GLenum = 0x84FF;
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WrapMode {
+    Repeat,
+    MirroredRepeat,
+    ClampToEdge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Filter {
+    Nearest,
+    Linear,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MinFilter {
+    Nearest,
+    Linear,
+    NearestMipmapNearest,
+    LinearMipmapNearest,
+    NearestMipmapLinear,
+    LinearMipmapLinear,
+}
+
+#[derive(Debug, Clone)]
+pub struct TextureOptions {
+    pub wrap: WrapMode,
+    pub min_filter: MinFilter,
+    pub mag_filter: Filter,
+    pub anisotropy: f32,
+    pub mip_filter: MipFilter,
+}
+
+impl TextureOptions {
+    pub fn new() -> Self {
+        Self {
+            wrap: WrapMode::Repeat,
+            min_filter: MinFilter::LinearMipmapLinear,
+            mag_filter: Filter::Linear,
+            anisotropy: 1.0,
+            mip_filter: MipFilter::Box,
+        }
+    }
+
+    pub fn apply(&self, target: gl::types::GLenum) {
+        unsafe {
+            gl::TexParameteri(target, gl::TEXTURE_MAG_FILTER, self.mag_filter.to_gl() as i32);
+            gl::TexParameteri(target, gl::TEXTURE_MIN_FILTER, self.min_filter.to_gl() as i32);
+            gl::TexParameteri(target, gl::TEXTURE_WRAP_S, self.wrap.to_gl() as i32);
+            gl::TexParameteri(target, gl::TEXTURE_WRAP_T, self.wrap.to_gl() as i32);
+            if self.anisotropy > 1.0 {
+                let mut max = 1.0;
+                gl::GetFloatv(MAX_TEXTURE_MAX_ANISOTROPY_EXT, &mut max);
+                // Without the extension the query fails and max stays at 1.0.
+                while gl::GetError() != gl::NO_ERROR {}
+                if max > 1.0 {
+                    gl::TexParameterf(target, TEXTURE_MAX_ANISOTROPY_EXT, self.anisotropy.min(max));
+                }
+            }
+        }
+    }
+}
+
+impl WrapMode {
+    fn to_gl(self) -> gl::types::GLenum {
+        match self {
+            WrapMode::Repeat => gl::REPEAT,
+            WrapMode::MirroredRepeat => gl::MIRRORED_REPEAT,
+            WrapMode::ClampToEdge => gl::CLAMP_TO_EDGE,
+        }
+    }
+}
+
+impl Filter {
+    fn to_gl(self) -> gl::types::GLenum {
+        match self {
+            Filter::Nearest => gl::NEAREST,
+            Filter::Linear => gl::LINEAR,
+        }
+    }
+}
+
+impl MinFilter {
+    fn to_gl(self) -> gl::types::GLenum {
+        match self {
+            MinFilter::Nearest => gl::NEAREST,
+            MinFilter::Linear => gl::LINEAR,
+            MinFilter::NearestMipmapNearest => gl::NEAREST_MIPMAP_NEAREST,
+            MinFilter::LinearMipmapNearest => gl::LINEAR_MIPMAP_NEAREST,
+            MinFilter::NearestMipmapLinear => gl::NEAREST_MIPMAP_LINEAR,
+            MinFilter::LinearMipmapLinear => gl::LINEAR_MIPMAP_LINEAR,
+        }
+    }
+
+    pub fn uses_mipmap(&self) -> bool {
+        !matches!(self, MinFilter::Nearest | MinFilter::Linear)
+    }
+}
+
+impl FromStr for WrapMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "repeat" => Ok(WrapMode::Repeat),
+            "mirror" => Ok(WrapMode::MirroredRepeat),
+            "clamp" => Ok(WrapMode::ClampToEdge),
+            _ => Err(format!("error: unknown wrap mode: {}", s)),
+        }
+    }
+}
+
+impl FromStr for Filter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nearest" => Ok(Filter::Nearest),
+            "linear" => Ok(Filter::Linear),
+            _ => Err(format!("error: unknown filter: {}", s)),
+        }
+    }
+}
+
+impl FromStr for MinFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nearest" => Ok(MinFilter::Nearest),
+            "linear" => Ok(MinFilter::Linear),
+            "nearest-mipmap-nearest" => Ok(MinFilter::NearestMipmapNearest),
+            "linear-mipmap-nearest" => Ok(MinFilter::LinearMipmapNearest),
+            "nearest-mipmap-linear" => Ok(MinFilter::NearestMipmapLinear),
+            "linear-mipmap-linear" | "trilinear" => Ok(MinFilter::LinearMipmapLinear),
+            _ => Err(format!("error: unknown min filter: {}", s)),
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use std::str::FromStr;
+
+use super::noise::{Noise, hash2};
+use crate::img_loader::{Image, PixelFormat};
+use crate::font;
+
+
+// Further octaves are below the precision of an 8 bit texel.
+const MAX_OCTAVES: usize = 16;
+// Keeps the generated image (and its mipmaps) within what GL takes.
+const MAX_SIZE: usize = 4096;
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProceduralKind {
+    Checker,
+    UvGrid,
+    Perlin,
+    Simplex,
+    Worley,
+    Marble,
+    Wood,
+    Bricks,
+}
+
+// `cells` is the number of repetitions across the texture: checker and grid
+// cells, noise lattice cells, marble veins, wood rings or brick rows.
+#[derive(Debug, Clone)]
+pub struct Procedural {
+    pub kind: ProceduralKind,
+    pub size: usize,
+    pub cells: usize,
+    pub octaves: usize,
+    pub seed: u32,
+    pub turbulence: f32,
+    pub mortar: f32,
+}
+
+const ALL_KINDS: [ProceduralKind; 8] = [
+    ProceduralKind::UvGrid,
+    ProceduralKind::Checker,
+    ProceduralKind::Perlin,
+    ProceduralKind::Simplex,
+    ProceduralKind::Worley,
+    ProceduralKind::Marble,
+    ProceduralKind::Wood,
+    ProceduralKind::Bricks,
+];
+
+impl FromStr for ProceduralKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ALL_KINDS.iter()
+            .find(|kind| kind.name() == s)
+            .copied()
+            .ok_or_else(|| format!("error: unknown procedural texture: {}", s))
+    }
+}
+
+impl ProceduralKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProceduralKind::Checker => "checker",
+            ProceduralKind::UvGrid => "grid",
+            ProceduralKind::Perlin => "perlin",
+            ProceduralKind::Simplex => "simplex",
+            ProceduralKind::Worley => "worley",
+            ProceduralKind::Marble => "marble",
+            ProceduralKind::Wood => "wood",
+            ProceduralKind::Bricks => "bricks",
+        }
+    }
+}
+
+// "kind" or "kind:key=value,key=value"
+impl FromStr for Procedural {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, params) = s.split_once(':').unwrap_or((s, ""));
+        let mut procedural = Self::new(kind.parse()?);
+        for param in params.split(',').filter(|p| !p.is_empty()) {
+            let (key, value) = param.split_once('=')
+                .ok_or_else(|| format!("error: procedural parameter: {}", param))?;
+            let error = || format!("error: procedural parameter: {}", param);
+            match key {
+                "size" => procedural.size = value.parse().map_err(|_| error())?,
+                "cells" => procedural.cells = value.parse().map_err(|_| error())?,
+                "octaves" => procedural.octaves = value.parse().map_err(|_| error())?,
+                "seed" => procedural.seed = value.parse().map_err(|_| error())?,
+                "turbulence" => procedural.turbulence = value.parse().map_err(|_| error())?,
+                "mortar" => procedural.mortar = value.parse().map_err(|_| error())?,
+                _ => return Err(error()),
+            }
+        }
+        if procedural.size == 0 || procedural.cells == 0 || procedural.octaves == 0 {
+            return Err(format!("error: procedural parameter: {}", s))
+        }
+        if procedural.size > MAX_SIZE {
+            return Err(format!("error: procedural: at most {} pixels", MAX_SIZE))
+        }
+        // A cell is at least one pixel.
+        if procedural.cells > procedural.size {
+            return Err(format!("error: procedural: {} cells do not fit in {} pixels", procedural.cells, procedural.size))
+        }
+        if procedural.octaves > MAX_OCTAVES {
+            return Err(format!("error: procedural: at most {} octaves", MAX_OCTAVES))
+        }
+        Ok(procedural)
+    }
+}
+
+impl Procedural {
+    pub fn new(kind: ProceduralKind) -> Self {
+        let (size, cells) = match kind {
+            ProceduralKind::UvGrid => (512, 8),
+            ProceduralKind::Marble => (256, 4),
+            ProceduralKind::Wood => (256, 10),
+            _ => (256, 8),
+        };
+        Self {
+            kind,
+            size,
+            cells,
+            octaves: 5,
+            seed: 1,
+            turbulence: if let ProceduralKind::Wood = kind { 0.05 } else { 5.0 },
+            mortar: 0.06,
+        }
+    }
+
+    pub fn all() -> Vec<Self> {
+        ALL_KINDS.iter().map(|kind| Self::new(*kind)).collect()
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.kind.name()
+    }
+
+    pub fn generate(&self) -> Image {
+        let mut image = Image::blank(self.size, self.size, PixelFormat::RGB8);
+        let noise = Noise::new(self.seed);
+        let size = self.size as f32;
+        let cells = self.cells as f32;
+        for y in 0..self.size {
+            for x in 0..self.size {
+                // Lattice coordinates, so that integer steps are cell borders.
+                let u = (x as f32 + 0.5) / size * cells;
+                let v = (y as f32 + 0.5) / size * cells;
+                let color = match self.kind {
+                    ProceduralKind::Checker => self.checker(u, v),
+                    ProceduralKind::UvGrid => self.uv_grid(u, v, x, y),
+                    ProceduralKind::Perlin => {
+                        let n = noise.fbm(u, v, self.octaves, self.cells as i32) * 0.5 + 0.5;
+                        [n, n, n]
+                    },
+                    ProceduralKind::Simplex => {
+                        let n = noise.simplex_fbm(u, v, self.octaves) * 0.5 + 0.5;
+                        [n, n, n]
+                    },
+                    ProceduralKind::Worley => {
+                        let (f1, f2) = noise.worley(u, v, self.cells as i32);
+                        let edge = ((f2 - f1) * 4.0).min(1.0);
+                        [f1.min(1.0), f1.min(1.0) * 0.6 + edge * 0.4, edge]
+                    },
+                    ProceduralKind::Marble => self.marble(&noise, u, v),
+                    ProceduralKind::Wood => self.wood(&noise, x as f32 / size, y as f32 / size),
+                    ProceduralKind::Bricks => self.bricks(u, v),
+                };
+                image.set_pixel(x, y, [color[0], color[1], color[2], 1.0]);
+            }
+        }
+        if let ProceduralKind::UvGrid = self.kind {
+            self.number_cells(&mut image);
+        }
+        image
+    }
+
+    fn checker(&self, u: f32, v: f32) -> [f32; 3] {
+        if (u.floor() as i32 + v.floor() as i32) % 2 == 0 {
+            [0.9, 0.9, 0.9]
+        } else {
+            [0.1, 0.1, 0.1]
+        }
+    }
+
+    // Cells get a hue from their column and a brightness from their row, so a
+    // mirrored or rotated mapping is easy to see. Cell 0 is at the UV origin.
+    fn uv_grid(&self, u: f32, v: f32, x: usize, y: usize) -> [f32; 3] {
+        let line = (self.size / self.cells / 64).max(1);
+        let cell_size = self.size / self.cells;
+        if x % cell_size < line || y % cell_size < line {
+            return [0.05, 0.05, 0.05]
+        }
+        let hue = u.floor() / self.cells as f32;
+        let value = 0.45 + 0.5 * (v.floor() + 1.0) / self.cells as f32;
+        let checker = if (u.floor() as i32 + v.floor() as i32) % 2 == 0 { 1.0 } else { 0.85 };
+        hsv_to_rgb(hue, 0.6, value * checker)
+    }
+
+    fn number_cells(&self, image: &mut Image) {
+        let cell_size = self.size / self.cells;
+        let scale = (cell_size / 32).max(1);
+        let margin = (self.size / self.cells / 64).max(1) + scale;
+        for row in 0..self.cells {
+            for col in 0..self.cells {
+                let text = (row * self.cells + col).to_string();
+                if font::text_width(&text, scale) + margin > cell_size {
+                    continue;
+                }
+                font::draw_text(
+                    image,
+                    col * cell_size + margin,
+                    row * cell_size + margin,
+                    &text,
+                    scale,
+                    [1.0, 1.0, 1.0, 1.0],
+                );
+            }
+        }
+    }
+
+    fn marble(&self, noise: &Noise, u: f32, v: f32) -> [f32; 3] {
+        let period = self.cells as i32 * 2;
+        let n = noise.fbm(u * 2.0, v * 2.0, self.octaves, period);
+        let t = ((u + v) * std::f32::consts::PI + self.turbulence * n).sin() * 0.5 + 0.5;
+        let t = t.powf(0.4);
+        mix([0.25, 0.25, 0.3], [0.95, 0.94, 0.92], t)
+    }
+
+    fn wood(&self, noise: &Noise, x: f32, y: f32) -> [f32; 3] {
+        let n = noise.fbm(x * 4.0, y * 4.0, self.octaves, 4);
+        let dx = x - 0.5;
+        let dy = y - 0.5;
+        let distance = (dx * dx + dy * dy).sqrt() + self.turbulence * n;
+        let ring = (distance * self.cells as f32 * 2.0).fract();
+        let t = (ring * std::f32::consts::TAU).sin() * 0.5 + 0.5;
+        mix([0.36, 0.2, 0.09], [0.72, 0.5, 0.28], t.powf(3.0))
+    }
+
+    fn bricks(&self, u: f32, v: f32) -> [f32; 3] {
+        // Bricks are twice as wide as they are tall.
+        let row = v.floor();
+        let offset = if row as i32 % 2 == 0 { 0.0 } else { 0.5 };
+        let bu = u / 2.0 + offset;
+        let column = bu.floor();
+        let fu = bu - column;
+        let fv = v - row;
+        if fu < self.mortar / 2.0 || fv < self.mortar {
+            return [0.75, 0.73, 0.7]
+        }
+        let columns = (self.cells / 2).max(1) as i32;
+        let h = hash2((column as i32).rem_euclid(columns), row as i32, self.seed);
+        let shade = 0.8 + 0.2 * (h & 0xff) as f32 / 255.0;
+        [0.62 * shade, 0.25 * shade, 0.18 * shade]
+    }
+}
+
+fn mix(a: [f32; 3], b: [f32; 3], t: f32) -> [f32; 3] {
+    [
+        a[0] + (b[0] - a[0]) * t,
+        a[1] + (b[1] - a[1]) * t,
+        a[2] + (b[2] - a[2]) * t,
+    ]
+}
+
+fn hsv_to_rgb(h: f32, s: f32, v: f32) -> [f32; 3] {
+    let h = h.fract() * 6.0;
+    let i = h.floor();
+    let f = h - i;
+    let p = v * (1.0 - s);
+    let q = v * (1.0 - s * f);
+    let t = v * (1.0 - s * (1.0 - f));
+    match i as i32 {
+        0 => [v, t, p],
+        1 => [q, v, p],
+        2 => [p, v, t],
+        3 => [p, q, v],
+        4 => [t, p, v],
+        _ => [v, p, q],
+    }
+}